@@ -61,11 +61,11 @@ fn main() {
     };
     let mut server = Server::from_timeouts(timeouts);
     server.run(addr, router).unwrap_or_else(|err| {
-        error!("running wruster {}", err.to_string());
+        error!("running wruster {}", err);
         process::exit(1);
     });
     server.wait().unwrap_or_else(|err| {
-        error!("error running wruster {}", err.to_string());
+        error!("error running wruster {}", err);
         process::exit(1);
     });
     process::exit(0);
@@ -2,12 +2,14 @@ use std::process;
 use std::process::exit;
 use std::time::Duration;
 
-use clap::{command, Parser};
+use clap::Parser;
 use wruster::http;
 use wruster::router;
 use wruster::router::HttpHandler;
 use wruster::{Certificate, PrivateKey, Server, Timeouts};
-use wruster_handlers::{log_middleware, serve_static};
+use wruster_handlers::{
+    log_middleware, serve_static_with_options, StaticOptions, DEFAULT_INDEX_FILE,
+};
 
 #[macro_use]
 extern crate log;
@@ -32,17 +34,42 @@ struct Cli {
     /// connections using TLS.
     #[arg(long, requires = "tls_private")]
     tls_cert: Option<String>,
+    /// Lists the contents of the directories without an index.html file.
+    ///
+    /// The listing is returned in HTML or, if the Accept header of the
+    /// request prefers it, in JSON.
+    #[arg(long)]
+    autoindex: bool,
+    /// Serves the index.html file of the directory for the paths that don't
+    /// exist and don't have an extension.
+    ///
+    /// Allows serving single page applications that handle the routing in
+    /// the client.
+    #[arg(long)]
+    spa: bool,
+}
+
+impl Cli {
+    fn static_options(&self) -> StaticOptions {
+        StaticOptions {
+            autoindex: self.autoindex,
+            fallback_file: self.spa.then(|| String::from(DEFAULT_INDEX_FILE)),
+            ..StaticOptions::default()
+        }
+    }
 }
 
 fn main() {
     env_logger::init();
     let cli = Cli::parse();
+    let options = cli.static_options();
     let addr = cli.addres;
     let dir = cli.directory;
 
     let routes = router::Router::new();
-    let serve_dir: HttpHandler =
-        log_middleware(Box::new(move |request| serve_static(&dir, request)));
+    let serve_dir: HttpHandler = log_middleware(Box::new(move |request| {
+        serve_static_with_options(&dir, &options, request)
+    }));
     routes.add("/", http::HttpMethod::GET, serve_dir);
     let timeouts = Timeouts {
         write_response_timeout: Duration::from_secs(10),
@@ -71,12 +98,28 @@ fn main() {
         None => server.run(&addr, routes),
     };
     if let Err(err) = running {
-        error!("error running wruster {}", err.to_string());
+        error!("error running wruster {}", err);
         process::exit(1);
     }
     if let Err(err) = server.wait() {
-        error!("error running wruster {}", err.to_string());
+        error!("error running wruster {}", err);
         process::exit(1);
     };
     process::exit(0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spa_serves_the_index_file_as_fallback() {
+        let cli = Cli::try_parse_from(["wrustatic", "localhost:8085", "./"]).unwrap();
+        assert_eq!(cli.static_options(), StaticOptions::default());
+
+        let cli = Cli::try_parse_from(["wrustatic", "--spa", "localhost:8085", "./"]).unwrap();
+        let options = cli.static_options();
+        assert_eq!(options.fallback_file.as_deref(), Some(DEFAULT_INDEX_FILE));
+        assert!(!options.autoindex);
+    }
+}
@@ -19,11 +19,11 @@ fn main() {
     routes.add("/", http::HttpMethod::GET, handler);
     let mut server = Server::new();
     if let Err(err) = server.run("127.0.0.1:8082", routes) {
-        error!("error running wruster {}", err.to_string());
+        error!("error running wruster {}", err);
         process::exit(1);
     };
     if let Err(err) = server.wait() {
-        error!("error running wruster {}", err.to_string());
+        error!("error running wruster {}", err);
         process::exit(1);
     };
     process::exit(0);
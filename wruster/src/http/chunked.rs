@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+use std::io::{self, prelude::*};
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+use super::errors::HttpError;
+use super::headers::Headers;
+use super::limits::Limits;
+use super::MessageChar;
+
+// The maximum number of hex digits accepted in a chunk size, enough to
+// represent any u64.
+const MAX_CHUNK_SIZE_DIGITS: usize = 16;
+
+// The maximum size of the chunks written by the write_chunked function.
+const WRITE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Holds the trailer fields of a chunked body. When reading, the trailers are
+/// set by the [`ChunkedReader`] once the last chunk has been read, it is
+/// shared between the reader and the [`super::Body`] that owns it.
+pub(crate) type Trailers = Rc<RefCell<Option<Headers>>>;
+
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    Size,
+    Data(u64),
+    DataEnd,
+    Done,
+    TooLarge,
+}
+
+/**
+Decodes a message body encoded using the ``chunked`` transfer coding as
+specified in: <https://datatracker.ietf.org/doc/html/rfc7230#section-4.1>.
+The reader never reads past the end of the chunked body so the underlying
+reader can be used to read the next message in the connection. The size of
+the decoded content and the trailers are checked against the given
+[`Limits`], a chunk size line, including its extensions, can't be longer
+than [`Limits::max_header_line_length`].
+*/
+pub(crate) struct ChunkedReader<R: BufRead> {
+    from: R,
+    state: State,
+    trailers: Trailers,
+    limits: Limits,
+    size: u64,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    pub fn new(from: R, limits: Limits) -> Self {
+        ChunkedReader {
+            from,
+            state: State::Size,
+            trailers: Rc::new(RefCell::new(None)),
+            limits,
+            size: 0,
+        }
+    }
+
+    pub fn trailers(&self) -> Trailers {
+        Rc::clone(&self.trailers)
+    }
+
+    fn read_chunk_size(&mut self) -> io::Result<u64> {
+        // chunk          = chunk-size [ chunk-ext ] CRLF
+        //                  chunk-data CRLF
+        // chunk-size     = 1*HEXDIG
+        // last-chunk     = 1*("0") [ chunk-ext ] CRLF
+        let max_len = self.limits.max_header_line_length;
+        let mut line = Vec::new();
+        (&mut self.from)
+            .take(max_len as u64)
+            .read_until(b'\n', &mut line)?;
+        if line.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        let len = line.len();
+        if line[len - 1] != b'\n' && len >= max_len {
+            debug!("maximum chunk size line length exceeded");
+            self.state = State::TooLarge;
+            return Err(body_too_large());
+        }
+        if len < 3 || line[len - 2] != b'\r' || line[len - 1] != b'\n' {
+            return Err(invalid_data("invalid chunk size line"));
+        }
+        let line = &line[..len - 2];
+        let (size, extensions) = match line.iter().position(|c| *c == b';') {
+            None => (line, None),
+            Some(pos) => (&line[..pos], Some(&line[pos + 1..])),
+        };
+        // Chunk extensions are parsed to validate them, but their values are
+        // not used.
+        if let Some(extensions) = extensions {
+            parse_chunk_extensions(extensions)?;
+        }
+        let size = String::from_utf8_lossy(size);
+        let size = size.trim_end_matches(|c: char| c.is_optional_white_space());
+        if size.is_empty() || size.len() > MAX_CHUNK_SIZE_DIGITS {
+            return Err(invalid_data("invalid chunk size"));
+        }
+        if !size.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid_data("invalid chunk size"));
+        }
+        u64::from_str_radix(size, 16).map_err(|_| invalid_data("invalid chunk size"))
+    }
+
+    fn read_chunk_end(&mut self) -> io::Result<()> {
+        let mut end = [0; 2];
+        self.from.read_exact(&mut end)?;
+        if &end != b"\r\n" {
+            return Err(invalid_data("invalid chunk data termination"));
+        }
+        Ok(())
+    }
+
+    fn read_trailers(&mut self) -> io::Result<()> {
+        // chunked-body   = *chunk
+        //                  last-chunk
+        //                  trailer-part
+        //                  CRLF
+        // trailer-part   = *( header-field CRLF )
+        let trailers = Headers::read_from_with_limits(&mut self.from, &self.limits)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        debug!("chunked body trailers parsed: {:?}", trailers);
+        *self.trailers.borrow_mut() = Some(trailers);
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                // Once a limit is exceeded the rest of the body is not read.
+                State::TooLarge => return Err(body_too_large()),
+                State::Size => {
+                    let size = self.read_chunk_size()?;
+                    debug!("chunk size read: {}", size);
+                    if size == 0 {
+                        self.read_trailers()?;
+                        self.state = State::Done;
+                        return Ok(0);
+                    }
+                    self.size = self.size.saturating_add(size);
+                    if self.size > self.limits.max_body_size {
+                        self.state = State::TooLarge;
+                        return Err(body_too_large());
+                    }
+                    self.state = State::Data(size);
+                }
+                State::DataEnd => {
+                    self.read_chunk_end()?;
+                    self.state = State::Size;
+                }
+                State::Data(remaining) => {
+                    if buf.is_empty() {
+                        return Ok(0);
+                    }
+                    let max = std::cmp::min(buf.len() as u64, remaining) as usize;
+                    let n = self.from.read(&mut buf[..max])?;
+                    if n == 0 {
+                        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                    }
+                    let remaining = remaining - n as u64;
+                    self.state = match remaining {
+                        0 => State::DataEnd,
+                        remaining => State::Data(remaining),
+                    };
+                    return Ok(n);
+                }
+            }
+        }
+    }
+}
+
+/**
+Writes the content of ``from`` to ``to`` using the ``chunked`` transfer coding,
+the content is written in chunks of, at most, [`WRITE_CHUNK_SIZE`] bytes
+followed by the last chunk and the given trailers, if any. Each chunk is
+flushed as soon as it's written, so the content read from a stream reaches the
+peer without waiting for more data.
+*/
+pub(crate) fn write_chunked<R: Read, W: Write>(
+    from: &mut R,
+    to: &mut W,
+    trailers: Option<&Headers>,
+) -> io::Result<()> {
+    let mut buf = vec![0; WRITE_CHUNK_SIZE];
+    // Each chunk, including its size line and its termination, is written
+    // with a single call.
+    let mut chunk = Vec::with_capacity(WRITE_CHUNK_SIZE + 32);
+    loop {
+        let n = match from.read(&mut buf) {
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if n == 0 {
+            break;
+        }
+        chunk.clear();
+        write!(chunk, "{:x}\r\n", n)?;
+        chunk.extend_from_slice(&buf[..n]);
+        chunk.extend_from_slice(b"\r\n");
+        to.write_all(&chunk)?;
+        to.flush()?;
+    }
+    let mut last = b"0\r\n".to_vec();
+    match trailers {
+        Some(trailers) => trailers
+            .write(&mut last)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?,
+        None => last.extend_from_slice(b"\r\n"),
+    };
+    to.write_all(&last)?;
+    to.flush()
+}
+
+fn parse_chunk_extensions(extensions: &[u8]) -> io::Result<()> {
+    // chunk-ext      = *( ";" chunk-ext-name [ "=" chunk-ext-val ] )
+    // chunk-ext-name = token
+    // chunk-ext-val  = token / quoted-string
+    // A quoted-string can contain a ";", so the extensions are tokenized
+    // instead of split.
+    let extensions = String::from_utf8_lossy(extensions);
+    let mut chars = extensions.chars().peekable();
+    loop {
+        skip_white_space(&mut chars);
+        if read_token(&mut chars).is_empty() {
+            return Err(invalid_data("invalid chunk extension name"));
+        }
+        skip_white_space(&mut chars);
+        match chars.next() {
+            None => return Ok(()),
+            Some(';') => continue,
+            Some('=') => (),
+            Some(_) => return Err(invalid_data("invalid chunk extension name")),
+        }
+        skip_white_space(&mut chars);
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            read_quoted_string(&mut chars)?;
+        } else if read_token(&mut chars).is_empty() {
+            return Err(invalid_data("invalid chunk extension value"));
+        }
+        skip_white_space(&mut chars);
+        match chars.next() {
+            None => return Ok(()),
+            Some(';') => continue,
+            Some(_) => return Err(invalid_data("invalid chunk extension value")),
+        }
+    }
+}
+
+fn skip_white_space(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_optional_white_space()).is_some() {}
+}
+
+fn read_token(chars: &mut Peekable<Chars>) -> String {
+    let mut token = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_valid_token_char()) {
+        token.push(c);
+    }
+    token
+}
+
+// Reads the rest of a quoted-string after its opening quote.
+fn read_quoted_string(chars: &mut Peekable<Chars>) -> io::Result<()> {
+    // quoted-string  = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+    // qdtext         = HTAB / SP /%x21 / %x23-5B / %x5D-7E / obs-text
+    // quoted-pair    = "\" ( HTAB / SP / VCHAR / obs-text )
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some(c) if c.is_valid_field_content() => (),
+                _ => return Err(invalid_data("invalid chunk extension value")),
+            },
+            Some(c) if c.is_valid_field_content() => (),
+            _ => return Err(invalid_data("invalid chunk extension value")),
+        }
+    }
+}
+
+fn body_too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, HttpError::BodyTooLarge)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -174,9 +174,11 @@ where
     }
 }
 
-trait EnsureThreadShareable: Send + Sync {}
-
-impl EnsureThreadShareable for Pool<String> {}
+// Ensures at compile time that the pool can be shared between threads.
+const _: fn() = || {
+    fn ensure_thread_shareable<T: Send + Sync>() {}
+    ensure_thread_shareable::<Pool<String>>();
+};
 
 #[cfg(test)]
 mod test {
@@ -1,4 +1,5 @@
 #![allow(missing_docs)]
+use std::io::{self, Cursor, Read};
 use std::net::SocketAddr;
 use std::net::TcpStream;
 use std::net::ToSocketAddrs;
@@ -24,6 +25,11 @@ pub const DEFAULT_READ_RESPONSE_TIMEOUT: time::Duration = time::Duration::from_s
 /// Defines the default max time for a request to be written.
 pub const DEFAULT_WRITE_REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(60);
 
+/// Defines the default max time to wait for a ``100 Continue`` interim
+/// response before sending the body of a request with the
+/// ``Expect: 100-continue`` header.
+pub const DEFAULT_EXPECT_CONTINUE_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
 pub struct ClientResponse {
     response: Response,
     conn: TcpStream,
@@ -106,26 +112,83 @@ impl<'a> Client {
 
         let conn = conn.try_clone().map_err(HttpError::from)?;
         let response_conn = conn.try_clone().map_err(HttpError::from)?;
+        let continue_conn = conn.try_clone().map_err(HttpError::from)?;
         let mut stream = TimeoutStream::from(conn, Some(read_timeout), Some(write_timeout));
-        request.write(&mut stream)?;
+        let is_head = request.method == HttpMethod::HEAD;
+        let mut request = request;
+        request.write_head(&mut stream)?;
         stream.flush().map_err(HttpError::from)?;
-        let stream = Box::new(stream);
-        let response = match Response::read_from(stream) {
-            Ok(response) => response,
-            Err(err) => return Err(err),
-        };
+        let mut pool = Arc::downgrade(&self.connection_pool);
+        let mut head = Vec::new();
+        if request.expects_continue() && request.body.is_some() {
+            head = Self::wait_continue(&continue_conn)?;
+        }
+        // If the server answered with a final response instead of 100
+        // Continue, the body is not sent, so the connection can't be reused.
+        if head.is_empty() {
+            if let Some(body) = request.body.as_mut() {
+                body.write(&mut stream)?;
+                stream.flush().map_err(HttpError::from)?;
+            }
+        } else {
+            pool = Weak::new();
+        }
+        let stream = Box::new(Cursor::new(head).chain(stream));
+        let mut response = Response::read_from(stream)?;
+        // The response to a HEAD request never has a body, even if it
+        // contains a Content-Length header.
+        if is_head {
+            response.body = None;
+        }
         // TODO: when the response does not have body we can just return back
         // the connection to the pool here.
-        let response_pool = Arc::clone(&self.connection_pool);
         let response = ClientResponse {
             response,
             conn: response_conn,
-            pool: Arc::downgrade(&response_pool),
+            pool,
             addr: addr.to_string(),
         };
         Ok(response)
     }
 
+    // Waits for the interim response to a request with the
+    // Expect: 100-continue header. If the server answers with 100 Continue,
+    // or doesn't answer before DEFAULT_EXPECT_CONTINUE_TIMEOUT, it returns an
+    // empty vector, otherwise it returns the bytes of the head of the final
+    // response sent by the server.
+    fn wait_continue(conn: &TcpStream) -> Result<Vec<u8>, HttpError> {
+        let mut conn = conn;
+        conn.set_read_timeout(Some(DEFAULT_EXPECT_CONTINUE_TIMEOUT))
+            .map_err(HttpError::from)?;
+        let mut head = Vec::new();
+        let mut c = [0; 1];
+        // The head is read byte by byte so no byte of the final response
+        // body is consumed.
+        while !head.ends_with(b"\r\n\r\n") {
+            match conn.read(&mut c) {
+                Ok(0) => return Err(HttpError::ConnectionClosed),
+                Ok(_) => head.push(c[0]),
+                Err(err)
+                    if head.is_empty()
+                        && matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) =>
+                {
+                    debug!("timeout waiting for 100 continue, sending body");
+                    return Ok(head);
+                }
+                Err(err) => return Err(HttpError::from(err)),
+            }
+        }
+        let status = head.split(|c| *c == b' ').nth(1).unwrap_or_default();
+        if status == b"100" {
+            debug!("100 continue received");
+            head.clear();
+        }
+        Ok(head)
+    }
+
     fn connect(addr: &str) -> Result<TcpStream, HttpError> {
         let addrs = addr.to_socket_addrs().map_err(HttpError::from)?;
         let addrs = addrs.collect::<Vec<SocketAddr>>();
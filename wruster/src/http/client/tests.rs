@@ -22,6 +22,64 @@ fn client_write_run_post_body() {
     server.shutdown().expect("Error shutting down server");
 }
 
+#[test]
+fn client_write_run_post_chunked_body() {
+    let handler = handler_from_check_body(|content| String::from_utf8_lossy(&content) == "test");
+    let (server, addr) = run_server(handler, HttpMethod::POST, "/");
+
+    let c = Client::new();
+    let content = std::io::Cursor::new("test");
+    let body = Body::chunked(Some(mime::TEXT_PLAIN), Box::new(content));
+    let request = Request::from_body(body, HttpMethod::POST, "/");
+    let response = c.run(&addr, request).expect("Error running request");
+
+    assert_eq!(response.status, StatusCode::OK);
+
+    server.shutdown().expect("Error shutting down server");
+}
+
+#[test]
+fn client_waits_for_continue() {
+    let handler = handler_from_check_body(|content| String::from_utf8_lossy(&content) == "test");
+    let (server, addr) = run_server(handler, HttpMethod::POST, "/");
+
+    let c = Client::new();
+    let body = Body::from("test", mime::TEXT_PLAIN);
+    let mut request = Request::from_body(body, HttpMethod::POST, "/");
+    request.headers.add(Header::new("Expect", "100-continue"));
+    let started = time::Instant::now();
+    let response = c.run(&addr, request).expect("Error running request");
+
+    assert_eq!(response.status, StatusCode::OK);
+    // The body must be sent when the 100 Continue is received, without
+    // waiting for the timeout.
+    assert!(started.elapsed() < DEFAULT_EXPECT_CONTINUE_TIMEOUT);
+
+    server.shutdown().expect("Error shutting down server");
+}
+
+#[test]
+fn client_does_not_send_body_when_rejected() {
+    let handler: HttpHandler =
+        Box::new(|_| Response::from_status(StatusCode::RequestEntityTooLarge));
+    let (server, addr) = run_server(handler, HttpMethod::POST, "/");
+
+    let c = Client::new();
+    let body = Body::from("test", mime::TEXT_PLAIN);
+    let mut request = Request::from_body(body, HttpMethod::POST, "/");
+    request.headers.add(Header::new("Expect", "100-continue"));
+    let response = c.run(&addr, request).expect("Error running request");
+    assert_eq!(response.status, StatusCode::RequestEntityTooLarge);
+    drop(response);
+
+    // The connection is not returned to the pool as the body was not sent.
+    let connection_pool = c.connection_pool.lock().unwrap();
+    assert!(connection_pool.get(&addr).is_none());
+    drop(connection_pool);
+
+    server.shutdown().expect("Error shutting down server");
+}
+
 #[test]
 fn client_keeps_connection_alive() {
     let handler = handler_from_check_body(|content| String::from_utf8_lossy(&content) == "test");
@@ -0,0 +1,158 @@
+/*!
+Contains the helpers to evaluate the preconditions of the conditional
+requests, as specified in: <https://datatracker.ietf.org/doc/html/rfc7232>.
+*/
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::headers::Headers;
+use super::{parse_http_date, HttpMethod, Request, StatusCode};
+
+/**
+Evaluates the ``If-Match``, ``If-Unmodified-Since``, ``If-None-Match`` and
+``If-Modified-Since`` headers of a request, in the order defined in:
+<https://datatracker.ietf.org/doc/html/rfc7232#section-6>, against the
+validators of the selected representation of a resource. It returns the
+status code the request must be answered with if a precondition fails, or
+``None`` if the request must be processed normally. The dates are compared
+with a resolution of one second, and the headers with an invalid date are
+ignored.
+
+# Examples
+
+```
+use std::time::{Duration, UNIX_EPOCH};
+use wruster::http::conditional::evaluate_preconditions;
+use wruster::http::headers::Header;
+use wruster::http::{HttpMethod, Request, StatusCode};
+
+let mut request = Request::read_from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+request.headers.add(Header::new("If-None-Match", "W/\"1a\", \"2b\""));
+let last_modified = UNIX_EPOCH + Duration::from_secs(784111777);
+let status = evaluate_preconditions(&request, Some("\"2b\""), Some(last_modified));
+assert_eq!(status, Some(StatusCode::NotModified));
+let status = evaluate_preconditions(&request, Some("\"3c\""), Some(last_modified));
+assert_eq!(status, None);
+```
+*/
+pub fn evaluate_preconditions(
+    request: &Request,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> Option<StatusCode> {
+    let headers = &request.headers;
+    let is_get_or_head = matches!(request.method, HttpMethod::GET | HttpMethod::HEAD);
+    let last_modified = last_modified.map(unix_secs);
+    if let Some(tags) = entity_tags(headers, "If-Match") {
+        if !tags.matches(etag, strong_compare) {
+            return Some(StatusCode::PreconditionFailed);
+        }
+    } else if let (Some(since), Some(modified)) =
+        (header_date(headers, "If-Unmodified-Since"), last_modified)
+    {
+        if modified > since {
+            return Some(StatusCode::PreconditionFailed);
+        }
+    }
+    if let Some(tags) = entity_tags(headers, "If-None-Match") {
+        if !tags.matches(etag, weak_compare) {
+            return None;
+        }
+        return match is_get_or_head {
+            true => Some(StatusCode::NotModified),
+            false => Some(StatusCode::PreconditionFailed),
+        };
+    }
+    if !is_get_or_head {
+        return None;
+    }
+    match (header_date(headers, "If-Modified-Since"), last_modified) {
+        (Some(since), Some(modified)) if modified <= since => Some(StatusCode::NotModified),
+        _ => None,
+    }
+}
+
+/**
+Returns true if two entity-tags match using the strong comparison, that is:
+none of them is weak and their opaque tags are equal.
+*/
+pub fn strong_compare(a: &str, b: &str) -> bool {
+    !a.starts_with("W/") && !b.starts_with("W/") && a == b
+}
+
+/**
+Returns true if two entity-tags match using the weak comparison, that is:
+their opaque tags are equal, regardless of whether any of them is weak.
+*/
+pub fn weak_compare(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/**
+Returns the time, as the number of seconds since the unix epoch, of the value
+of a header containing an HTTP-date, or ``None`` if the header is not present
+or its value is not a valid date.
+*/
+pub(crate) fn header_date(headers: &Headers, name: &str) -> Option<u64> {
+    headers
+        .get_first(name)
+        .and_then(parse_http_date)
+        .map(unix_secs)
+}
+
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+enum EntityTags {
+    Any,
+    List(Vec<String>),
+}
+
+impl EntityTags {
+    fn matches(&self, etag: Option<&str>, compare: fn(&str, &str) -> bool) -> bool {
+        match (self, etag) {
+            (_, None) => false,
+            (EntityTags::Any, Some(_)) => true,
+            (EntityTags::List(tags), Some(etag)) => tags.iter().any(|tag| compare(tag, etag)),
+        }
+    }
+}
+
+// Parses a header with the value: "*" / 1#entity-tag. The entity-tags can
+// contain commas, so the values are not split as the ones of other lists.
+fn entity_tags(headers: &Headers, name: &str) -> Option<EntityTags> {
+    let values = headers.get(name)?;
+    if values.iter().any(|value| value.trim() == "*") {
+        return Some(EntityTags::Any);
+    }
+    let mut tags = Vec::new();
+    for value in values {
+        let mut rest = value.as_str();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+            if rest.is_empty() {
+                break;
+            }
+            let prefix = if rest.starts_with("W/") { 2 } else { 0 };
+            let opaque = match rest[prefix..].strip_prefix('"') {
+                // Invalid elements are skipped until the next comma.
+                None => {
+                    rest = rest.split_once(',').map(|(_, rest)| rest).unwrap_or("");
+                    continue;
+                }
+                Some(opaque) => opaque,
+            };
+            match opaque.find('"') {
+                None => break,
+                Some(end) => {
+                    let tag_end = prefix + 1 + end + 1;
+                    tags.push(rest[..tag_end].to_string());
+                    rest = &rest[tag_end..];
+                }
+            }
+        }
+    }
+    Some(EntityTags::List(tags))
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use super::date::format_http_date;
+use super::errors::HttpError;
+use super::headers::Headers;
+use super::MessageChar;
+
+/**
+Defines the values of the ``SameSite`` attribute of a cookie:
+<https://datatracker.ietf.org/doc/html/draft-ietf-httpbis-rfc6265bis#section-4.1.2.7>.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// The cookie is only sent in same-site requests.
+    Strict,
+    /// The cookie is also sent in top-level cross-site navigations.
+    Lax,
+    /// The cookie is sent in all the requests, it requires the cookie to be
+    /// [`Cookie::secure`].
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/**
+Represents a cookie to be sent to a client in a ``Set-Cookie`` header, as
+specified in: <https://datatracker.ietf.org/doc/html/rfc6265#section-4.1>.
+The attributes of the cookie are set using the builder methods, and the
+cookie is added to a response with [`super::Response::add_cookie`].
+
+# Examples
+
+```
+use std::time::Duration;
+use wruster::http::{Cookie, Response, SameSite, StatusCode};
+
+let cookie = Cookie::new("session", "38afes7a8")
+    .path("/")
+    .max_age(Duration::from_secs(3600))
+    .secure()
+    .http_only()
+    .same_site(SameSite::Lax);
+let mut response = Response::from_status(StatusCode::OK);
+response.add_cookie(&cookie).unwrap();
+assert_eq!(
+    response.headers.get_first("Set-Cookie"),
+    Some("session=38afes7a8; Path=/; Max-Age=3600; Secure; HttpOnly; SameSite=Lax")
+);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Creates a cookie with the given name and value and without attributes.
+    pub fn new(name: &str, value: &str) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            domain: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Returns the name of the cookie.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of the cookie.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Sets the ``Path`` attribute of the cookie.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Sets the ``Domain`` attribute of the cookie.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Sets the ``Expires`` attribute of the cookie.
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Sets the ``Max-Age`` attribute of the cookie, only the whole seconds
+    /// of the duration are sent. A zero duration removes the cookie from
+    /// the client.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the ``Secure`` attribute of the cookie.
+    pub fn secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    /// Sets the ``HttpOnly`` attribute of the cookie.
+    pub fn http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    /// Sets the ``SameSite`` attribute of the cookie.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /**
+    Checks that the cookie can be sent in a ``Set-Cookie`` header.
+
+    # Errors
+
+    Returns an [`HttpError::InvalidCookie`] if the name of the cookie is not a
+    token, the value contains chars not allowed in a cookie value, like
+    ``;``, ``,`` or spaces, an attribute contains a ``;`` or a control char, or
+    the ``SameSite`` attribute is ``None`` and the cookie is not secure.
+    */
+    pub fn validate(&self) -> Result<(), HttpError> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_valid_token_char()) {
+            let msg = format!("invalid cookie name {}", self.name);
+            return Err(HttpError::InvalidCookie(msg));
+        }
+        // cookie-value = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE )
+        let value = self
+            .value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(&self.value);
+        if !value.bytes().all(is_cookie_octet) {
+            let msg = format!("invalid value for cookie {}", self.name);
+            return Err(HttpError::InvalidCookie(msg));
+        }
+        let attributes = [self.path.as_ref(), self.domain.as_ref()];
+        let invalid_attribute = attributes
+            .iter()
+            .flatten()
+            .any(|attribute| attribute.chars().any(|c| c == ';' || c.is_ascii_control()));
+        if invalid_attribute {
+            let msg = format!("invalid attribute for cookie {}", self.name);
+            return Err(HttpError::InvalidCookie(msg));
+        }
+        if self.same_site == Some(SameSite::None) && !self.secure {
+            let msg = format!("cookie {} with SameSite=None must be secure", self.name);
+            return Err(HttpError::InvalidCookie(msg));
+        }
+        Ok(())
+    }
+}
+
+/// Formats the cookie as the value of a ``Set-Cookie`` header, without
+/// validating it, see [`Cookie::validate`].
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = self.path.as_ref() {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = self.domain.as_ref() {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        Ok(())
+    }
+}
+
+// cookie-octet = %x21 / %x23-2B / %x2D-3A / %x3C-5B / %x5D-7E
+fn is_cookie_octet(c: u8) -> bool {
+    matches!(c, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+/**
+Parses the ``Cookie`` headers of a request as specified in:
+<https://datatracker.ietf.org/doc/html/rfc6265#section-5.4>. The pairs
+without a ``=`` or with an empty name are ignored, the values enclosed in
+double quotes are unquoted and, if a cookie appears more than once, the first
+value is kept.
+*/
+pub(crate) fn parse_cookies(headers: &Headers) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+    let values = match headers.get("Cookie") {
+        None => return cookies,
+        Some(values) => values,
+    };
+    for pair in values.iter().flat_map(|value| value.split(';')) {
+        let (name, value) = match pair.split_once('=') {
+            None => continue,
+            Some((name, value)) => (name.trim(), value.trim()),
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        cookies
+            .entry(name.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    cookies
+}
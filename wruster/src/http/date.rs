@@ -0,0 +1,201 @@
+use std::cell::RefCell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+thread_local! {
+    // The last formatted date together with the second it corresponds to.
+    static CACHED_DATE: RefCell<(u64, String)> = const { RefCell::new((u64::MAX, String::new())) };
+}
+
+/**
+Formats a point in time as an HTTP-date in the IMF-fixdate format defined in:
+<https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.1>. The times
+before the unix epoch are formatted as the epoch.
+
+# Examples
+
+```
+use std::time::{Duration, UNIX_EPOCH};
+use wruster::http::format_http_date;
+
+let time = UNIX_EPOCH + Duration::from_secs(784111777);
+assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+```
+*/
+pub fn format_http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    format_secs(secs)
+}
+
+/**
+Parses an HTTP-date in any of the three formats that the spec requires the
+recipients to accept: IMF-fixdate, the obsolete RFC 850 format and the ANSI C
+``asctime()`` format, see: <https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.1>.
+It returns ``None`` if the date is invalid. The two digit years of the RFC 850
+format are interpreted as the ones between 1970 and 2069.
+
+# Examples
+
+```
+use std::time::{Duration, UNIX_EPOCH};
+use wruster::http::parse_http_date;
+
+let time = UNIX_EPOCH + Duration::from_secs(784111777);
+assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(time));
+assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(time));
+assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(time));
+assert_eq!(parse_http_date("06 Nov 1994"), None);
+```
+*/
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    let date = date.trim();
+    let (day, month, year, time) = match date.split_once(", ") {
+        Some((weekday, rest)) => {
+            let parts: Vec<&str> = rest.split(' ').collect();
+            match parts[..] {
+                // IMF-fixdate: Sun, 06 Nov 1994 08:49:37 GMT
+                [day, month, year, time, "GMT"]
+                    if DAYS.contains(&weekday) && day.len() == 2 && year.len() == 4 =>
+                {
+                    (day, month, parse_number(year)?, time)
+                }
+                // RFC 850: Sunday, 06-Nov-94 08:49:37 GMT
+                [date, time, "GMT"] => {
+                    let parts: Vec<&str> = date.split('-').collect();
+                    match parts[..] {
+                        [day, month, year] if day.len() == 2 && year.len() == 2 => {
+                            let year = parse_number(year)?;
+                            let year = if year < 70 { year + 2000 } else { year + 1900 };
+                            (day, month, year, time)
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        // asctime: Sun Nov  6 08:49:37 1994
+        None => {
+            let parts: Vec<&str> = date.split_whitespace().collect();
+            match parts[..] {
+                [weekday, month, day, time, year]
+                    if DAYS.contains(&weekday) && year.len() == 4 && day.len() <= 2 =>
+                {
+                    (day, month, parse_number(year)?, time)
+                }
+                _ => return None,
+            }
+        }
+    };
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let day = parse_number(day)?;
+    let parts: Vec<&str> = time.split(':').collect();
+    let (hour, minute, second) = match parts[..] {
+        [hour, minute, second] if hour.len() == 2 && minute.len() == 2 && second.len() == 2 => (
+            parse_number(hour)?,
+            parse_number(minute)?,
+            parse_number(second)?,
+        ),
+        _ => return None,
+    };
+    if year < 1970 || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    // A leap second is accepted as the last second of the minute.
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second.min(59);
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/**
+Returns the current time formatted as an HTTP-date, see [`format_http_date`].
+The formatted date is cached, so it's only formatted once per second.
+*/
+pub(crate) fn http_date_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    CACHED_DATE.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        if cached.0 != secs {
+            *cached = (secs, format_secs(secs));
+        }
+        cached.1.clone()
+    })
+}
+
+fn format_secs(secs: u64) -> String {
+    let days = secs / 86400;
+    let secs_of_day = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    // The 1st of January of 1970 was a Thursday.
+    let weekday = DAYS[((days + 4) % 7) as usize];
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        weekday,
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+// Converts a number of days since the unix epoch to a (year, month, day)
+// date in the proleptic Gregorian calendar, using the algorithm described in:
+// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Converts a (year, month, day) date in the proleptic Gregorian calendar, not
+// earlier than the unix epoch, to the number of days since the epoch, using
+// the algorithm described in:
+// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_number(digits: &str) -> Option<u64> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use std::sync::PoisonError;
 use std::{fmt, io};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 /// Defines the possible errors generated when reading and parsing a Request or a Response.
 pub enum HttpError {
     /// It's generated when any not controlled error is encountered when
@@ -18,6 +18,22 @@ pub enum HttpError {
     Timeout,
     /// It's generated when a syntactic error is found while reading a request.
     InvalidRequest(String),
+    /// It's generated when the request line of a request exceeds the
+    /// maximum length allowed, see [`super::Limits`].
+    RequestLineTooLong,
+    /// It's generated when a header line, the number of headers or the total
+    /// size of the headers of a request exceed the maximum allowed, see
+    /// [`super::Limits`].
+    HeadersTooLarge,
+    /// It's generated when the body of a request exceeds the maximum size
+    /// allowed, see [`super::Limits`].
+    BodyTooLarge,
+    /// It's generated when the version of a request is syntactically valid
+    /// but is not supported, see [`super::Version`].
+    VersionNotSupported(String),
+    /// It's generated when a cookie can't be sent in a ``Set-Cookie`` header,
+    /// see [`super::Cookie::validate`].
+    InvalidCookie(String),
 }
 
 impl fmt::Display for HttpError {
@@ -27,6 +43,13 @@ impl fmt::Display for HttpError {
             Self::ConnectionClosed => write!(f, "connection closed"),
             Self::Timeout => write!(f, "operation timeout"),
             HttpError::InvalidRequest(msg) => write!(f, "invalid request: {}", msg),
+            Self::RequestLineTooLong => write!(f, "request line too long"),
+            Self::HeadersTooLarge => write!(f, "request headers too large"),
+            Self::BodyTooLarge => write!(f, "request body too large"),
+            Self::VersionNotSupported(version) => {
+                write!(f, "http version not supported: {}", version)
+            }
+            Self::InvalidCookie(msg) => write!(f, "invalid cookie: {}", msg),
         }
     }
 }
@@ -38,7 +61,15 @@ impl From<io::Error> for HttpError {
         match err.kind() {
             io::ErrorKind::NotConnected => HttpError::ConnectionClosed,
             io::ErrorKind::TimedOut => HttpError::Timeout,
-            _ => HttpError::Unknown(err.to_string()),
+            // The errors found while decoding a body, e.g. when it exceeds
+            // the limits, are returned wrapped in an io::Error by its reader.
+            _ => match err
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<HttpError>())
+            {
+                Some(inner) => inner.clone(),
+                None => HttpError::Unknown(err.to_string()),
+            },
         }
     }
 }
@@ -0,0 +1,45 @@
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use super::{StatusCode, Version};
+
+/**
+Wraps the content of the body of a request that contains the
+``Expect: 100-continue`` header. The ``100 Continue`` interim response is
+written the first time the content is read, that is: when the handler of the
+request starts reading the body:
+<https://datatracker.ietf.org/doc/html/rfc7231#section-5.1.1>.
+*/
+pub(crate) struct ContinueReader<W: Write> {
+    content: Box<dyn Read>,
+    to: Option<W>,
+    sent: Rc<Cell<bool>>,
+}
+
+impl<W: Write> ContinueReader<W> {
+    pub fn new(content: Box<dyn Read>, to: W) -> Self {
+        ContinueReader {
+            content,
+            to: Some(to),
+            sent: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Returns a flag that is set to true when the interim response is sent.
+    pub fn sent(&self) -> Rc<Cell<bool>> {
+        Rc::clone(&self.sent)
+    }
+}
+
+impl<W: Write> Read for ContinueReader<W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(mut to) = self.to.take() {
+            debug!("sending 100 continue");
+            write!(to, "{} {}\r\n\r\n", Version::HTTP1_1, StatusCode::Continue)?;
+            to.flush()?;
+            self.sent.set(true);
+        }
+        self.content.read(buf)
+    }
+}
@@ -1,18 +1,24 @@
-use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
-use std::io;
-use std::io::prelude::*;
+use std::io::{self, BufRead, Read};
+
+use mime::Mime;
 
 use super::errors::HttpError::InvalidRequest;
 use super::errors::*;
+use super::limits::Limits;
 use super::HttpResult;
 use super::MessageChar;
 
-#[derive(Debug)]
-/// Holds a collection of HTTP headers.
+#[derive(Debug, Clone)]
+/**
+Holds a collection of HTTP headers. The names of the headers are compared
+case-insensitively, as specified in:
+<https://datatracker.ietf.org/doc/html/rfc7230#section-3.2>, and the headers
+are iterated, and written, in the order they were first added.
+*/
 pub struct Headers {
-    headers: HashMap<String, Vec<String>>,
+    headers: Vec<(String, Vec<String>)>,
 }
 
 impl Headers {
@@ -30,7 +36,7 @@ impl Headers {
     */
     pub fn new() -> Headers {
         Headers {
-            headers: HashMap::new(),
+            headers: Vec::new(),
         }
     }
 
@@ -55,11 +61,11 @@ impl Headers {
     ```
     */
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
-        self.headers.iter()
+        self.headers.iter().map(|(name, values)| (name, values))
     }
 
     /**
-    Reads the headers from an HTTP message in a [`io::BufReader`] according to
+    Reads the headers from an HTTP message in a type implementing [`io::BufRead`] according to
     the spec: <https://datatracker.ietf.org/doc/html/rfc7230>.
 
     # Errors
@@ -67,20 +73,50 @@ impl Headers {
     Returns a [`HttpError`] if the header does not conform to the spec: <https://datatracker.ietf.org/doc/html/rfc7230>
     or there is any problem reading from the ``to``parameter.
     */
-    pub fn read_from<T: io::Read>(from: &mut io::BufReader<T>) -> Result<Headers, HttpError> {
+    pub fn read_from<T: io::BufRead>(from: &mut T) -> Result<Headers, HttpError> {
+        Self::read_from_with_limits(from, &Limits::unlimited())
+    }
+
+    /**
+    Reads the headers from an HTTP message in a type implementing [`io::BufRead`] according to
+    the spec: <https://datatracker.ietf.org/doc/html/rfc7230>, enforcing the
+    limits related to the headers defined in the ``limits`` parameter.
+
+    # Errors
+
+    Returns a [`HttpError::HeadersTooLarge`] if the length of a header line,
+    the number of headers or the size of all the headers exceed the limits,
+    and a [`HttpError`] if the header does not conform to the spec or there
+    is any problem reading from the ``to`` parameter.
+    */
+    pub fn read_from_with_limits<T: io::BufRead>(
+        from: &mut T,
+        limits: &Limits,
+    ) -> Result<Headers, HttpError> {
         let mut headers = Self::new();
         // generic-message = start-line
         //                   *(message-header CRLF)
         //                   CRLF
         //                   [ message-body ]
         debug!("parsing headers");
+        let mut size = 0;
+        let mut count = 0;
         loop {
-            let header = Header::read_from(from)?;
+            let remaining = limits.max_headers_size - size;
+            let max_line = std::cmp::min(limits.max_header_line_length, remaining);
+            let line = Header::read_line(from, max_line)?;
+            size += line.len();
+            let header = Header::parse_header_line(line)?;
             match header {
                 None => {
                     break;
                 }
                 Some(header) => {
+                    count += 1;
+                    if count > limits.max_headers {
+                        debug!("maximum number of headers exceeded");
+                        return Err(HttpError::HeadersTooLarge);
+                    }
                     headers.add(header);
                 }
             };
@@ -105,10 +141,224 @@ impl Headers {
     ```
     */
     pub fn add(&mut self, header: Header) {
-        let name = header.name;
-        let content = header.value;
-        let values = self.headers.entry(name).or_default();
-        values.push(content);
+        match self.position(&header.name) {
+            Some(pos) => self.headers[pos].1.push(header.value),
+            None => self.headers.push((header.name, vec![header.value])),
+        }
+    }
+
+    /**
+    Sets the value of a header replacing all its current values, if any. If
+    the header was already in the collection it keeps its position.
+
+    # Examples
+    ```
+    use wruster::http::headers::{Headers, Header};
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept", "text/html"));
+    headers.add(Header::new("Accept", "text/plain"));
+    headers.set(Header::new("accept", "application/json"));
+    assert_eq!(headers.get("Accept"), Some(&vec![String::from("application/json")]));
+    ```
+    */
+    pub fn set(&mut self, header: Header) {
+        match self.position(&header.name) {
+            Some(pos) => self.headers[pos].1 = vec![header.value],
+            None => self.headers.push((header.name, vec![header.value])),
+        }
+    }
+
+    /**
+    Removes a header given its name, returning its values, if any.
+
+    # Examples
+    ```
+    use wruster::http::headers::{Headers, Header};
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept", "text/html"));
+    let values = headers.remove("accept");
+    assert_eq!(values, Some(vec![String::from("text/html")]));
+    assert_eq!(headers.get("Accept"), None);
+    ```
+    */
+    pub fn remove(&mut self, name: &str) -> Option<Vec<String>> {
+        let pos = self.position(name)?;
+        Some(self.headers.remove(pos).1)
+    }
+
+    /**
+    Adds the headers of ``other`` that are not present in the collection,
+    with all their values. The headers already present are left untouched.
+
+    # Examples
+    ```
+    use wruster::http::headers::{Headers, Header};
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Server", "custom"));
+    let mut defaults = Headers::new();
+    defaults.add(Header::new("Server", "wruster"));
+    defaults.add(Header::new("X-Frame-Options", "DENY"));
+    headers.add_missing(&defaults);
+    assert_eq!(headers.get_first("Server"), Some("custom"));
+    assert_eq!(headers.get_first("X-Frame-Options"), Some("DENY"));
+    ```
+    */
+    pub fn add_missing(&mut self, other: &Headers) {
+        for (name, values) in other.iter() {
+            if !self.contains(name) {
+                self.headers.push((name.clone(), values.clone()));
+            }
+        }
+    }
+
+    /// Returns true if the collection contains a header with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Returns the first value of a header given its name.
+    pub fn get_first(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .and_then(|values| values.first())
+            .map(|value| value.as_str())
+    }
+
+    /// Returns the number of different headers in the collection.
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns true if the collection does not contain any header.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /**
+    Returns the value of the ``Content-Length`` header, if present and valid.
+
+    # Examples
+    ```
+    use wruster::http::headers::{Headers, Header};
+
+    let mut headers = Headers::new();
+    headers.set_content_length(10);
+    assert_eq!(headers.content_length(), Some(10));
+    ```
+    */
+    pub fn content_length(&self) -> Option<u64> {
+        self.get_first("Content-Length")?.trim().parse().ok()
+    }
+
+    /// Sets the value of the ``Content-Length`` header.
+    pub fn set_content_length(&mut self, length: u64) {
+        self.set(Header::new("Content-Length", &length.to_string()))
+    }
+
+    /**
+    Returns the value of the ``Content-Type`` header, if present and valid.
+
+    # Examples
+    ```
+    use wruster::http::headers::Headers;
+
+    let mut headers = Headers::new();
+    headers.set_content_type(&mime::TEXT_PLAIN);
+    assert_eq!(headers.content_type(), Some(mime::TEXT_PLAIN));
+    ```
+    */
+    pub fn content_type(&self) -> Option<Mime> {
+        self.get_first("Content-Type")?.parse().ok()
+    }
+
+    /// Sets the value of the ``Content-Type`` header.
+    pub fn set_content_type(&mut self, mime: &Mime) {
+        self.set(Header::new("Content-Type", mime.as_ref()))
+    }
+
+    /// Returns the value of the ``Host`` header, if present.
+    pub fn host(&self) -> Option<&str> {
+        self.get_first("Host")
+    }
+
+    /// Sets the value of the ``Host`` header.
+    pub fn set_host(&mut self, host: &str) {
+        self.set(Header::new("Host", host))
+    }
+
+    /**
+    Returns the connection options in the ``Connection`` header in lower case,
+    as they are case-insensitive:
+    <https://datatracker.ietf.org/doc/html/rfc7230#section-6.1>.
+
+    # Examples
+    ```
+    use wruster::http::headers::{Headers, Header};
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Connection", "Keep-Alive, Upgrade"));
+    assert_eq!(headers.connection(), vec!["keep-alive", "upgrade"]);
+    ```
+    */
+    pub fn connection(&self) -> Vec<String> {
+        self.list_values("Connection")
+            .map(|option| option.to_ascii_lowercase())
+            .collect()
+    }
+
+    /// Sets the value of the ``Connection`` header.
+    pub fn set_connection(&mut self, connection: &str) {
+        self.set(Header::new("Connection", connection))
+    }
+
+    /// Returns the elements of the ``Accept`` header, if present.
+    pub fn accept(&self) -> Vec<&str> {
+        self.list_values("Accept").collect()
+    }
+
+    /// Sets the value of the ``Accept`` header.
+    pub fn set_accept(&mut self, accept: &str) {
+        self.set(Header::new("Accept", accept))
+    }
+
+    /// Returns the directives of the ``Cache-Control`` header, if present.
+    pub fn cache_control(&self) -> Vec<&str> {
+        self.list_values("Cache-Control").collect()
+    }
+
+    /// Sets the value of the ``Cache-Control`` header.
+    pub fn set_cache_control(&mut self, cache_control: &str) {
+        self.set(Header::new("Cache-Control", cache_control))
+    }
+
+    /// Returns the value of the ``Date`` header, if present.
+    pub fn date(&self) -> Option<&str> {
+        self.get_first("Date")
+    }
+
+    /// Sets the value of the ``Date`` header.
+    pub fn set_date(&mut self, date: &str) {
+        self.set(Header::new("Date", date))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|(header, _)| header.eq_ignore_ascii_case(name))
+    }
+
+    // Returns the elements of a header defined as a comma separated list:
+    // https://datatracker.ietf.org/doc/html/rfc7230#section-7, taking into
+    // account all the values of the header.
+    pub(crate) fn list_values(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.get(name)
+            .into_iter()
+            .flatten()
+            .flat_map(|value| value.split(','))
+            .map(|element| element.trim())
+            .filter(|element| !element.is_empty())
     }
 
     /**
@@ -135,7 +385,7 @@ impl Headers {
     ```
     */
     pub fn get(&self, name: &str) -> Option<&Vec<String>> {
-        self.headers.get(name)
+        self.position(name).map(|pos| &self.headers[pos].1)
     }
 
     /**
@@ -205,7 +455,26 @@ pub struct Header {
 
 impl Header {
     /**
-    Reads an header from an HTTP message in a [`io::BufReader`] according to
+    Creates a new header given its name and value.
+
+    # Examples
+
+    ```
+    use wruster::http::headers::Header;
+
+    let header = Header::new("Content-Type", "text/plain");
+    assert_eq!(header.name, "Content-Type");
+    ```
+    */
+    pub fn new(name: &str, value: &str) -> Header {
+        Header {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /**
+    Reads an header from an HTTP message in a type implementing [`io::BufRead`] according to
     the spec: <https://datatracker.ietf.org/doc/html/rfc7230>.
 
     # Examples
@@ -217,9 +486,14 @@ impl Header {
     Returns a [`HttpError`] if the header does not conform to the spec:
     <https://datatracker.ietf.org/doc/html/rfc7230>.
     */
-    pub fn read_from<T: io::Read>(
-        from: &mut io::BufReader<T>,
-    ) -> Result<Option<Header>, HttpError> {
+    pub fn read_from<T: io::BufRead>(from: &mut T) -> Result<Option<Header>, HttpError> {
+        let line = Header::read_line(from, usize::MAX)?;
+        Header::parse_header_line(line)
+    }
+
+    // Reads a header line, including the CRLF at the end, returning a
+    // HeadersTooLarge error if the line is longer than max_len bytes.
+    fn read_line<T: io::BufRead>(from: &mut T, max_len: usize) -> Result<Vec<u8>, HttpError> {
         //generic-message = start-line
         //                  *(message-header CRLF)
         //                   CRLF
@@ -227,20 +501,27 @@ impl Header {
         // https://www.rfc-editor.org/rfc/rfc7230#section-3.2.4
         let mut line = Vec::<u8>::new();
         loop {
+            let remaining = (max_len - line.len()) as u64;
             let mut header_chunk = Vec::<u8>::new();
-            from.read_until(b'\n', &mut header_chunk)
+            let n = (&mut *from)
+                .take(remaining)
+                .read_until(b'\n', &mut header_chunk)
                 .map_err(HttpError::from)?;
             line.append(&mut header_chunk);
-            debug!("header chunk read: {}", String::from_utf8_lossy(&line));
             let len = line.len();
-            if len < 2 {
-                continue;
-            }
-            if line[len - 1] == b'\n' && line[len - 2] == b'\r' {
+            if len >= 2 && line[len - 1] == b'\n' && line[len - 2] == b'\r' {
                 break;
             }
+            if len >= max_len {
+                debug!("maximum header line length exceeded");
+                return Err(HttpError::HeadersTooLarge);
+            }
+            if n == 0 {
+                return Err(HttpError::ConnectionClosed);
+            }
+            debug!("header chunk read: {}", String::from_utf8_lossy(&line));
         }
-        Header::parse_header_line(line)
+        Ok(line)
     }
 
     fn parse_header_line(line: Vec<u8>) -> Result<Option<Header>, HttpError> {
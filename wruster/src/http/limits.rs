@@ -0,0 +1,73 @@
+/// Defines the default maximum length, in bytes, of a request line.
+pub const DEFAULT_MAX_REQUEST_LINE_LENGTH: usize = 8 * 1024;
+
+/// Defines the default maximum length, in bytes, of a header line.
+pub const DEFAULT_MAX_HEADER_LINE_LENGTH: usize = 8 * 1024;
+
+/// Defines the default maximum number of headers in a request.
+pub const DEFAULT_MAX_HEADERS: usize = 100;
+
+/// Defines the default maximum size, in bytes, of all the headers of a
+/// request.
+pub const DEFAULT_MAX_HEADERS_SIZE: usize = 64 * 1024;
+
+/// Defines the default maximum size, in bytes, of the body of a request.
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 32 * 1024 * 1024;
+
+/**
+Defines the limits enforced when reading a request. When a limit is exceeded
+the request is rejected with a [`super::HttpError::RequestLineTooLong`],
+[`super::HttpError::HeadersTooLarge`] or [`super::HttpError::BodyTooLarge`]
+error.
+
+# Examples
+
+```
+use wruster::http::Limits;
+
+let limits = Limits {
+    max_body_size: 1024,
+    ..Limits::default()
+};
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of the request line, including the CRLF.
+    pub max_request_line_length: usize,
+    /// Maximum length of a header line, including the CRLF.
+    pub max_header_line_length: usize,
+    /// Maximum number of header lines.
+    pub max_headers: usize,
+    /// Maximum size of all the header lines, including the empty line at
+    /// the end of the headers.
+    pub max_headers_size: usize,
+    /// Maximum size of the body. For bodies encoded using the ``chunked``
+    /// transfer coding, the limit is checked while the body is read.
+    pub max_body_size: u64,
+}
+
+impl Limits {
+    /// Returns limits that don't restrict the size of the messages.
+    pub fn unlimited() -> Limits {
+        Limits {
+            max_request_line_length: usize::MAX,
+            max_header_line_length: usize::MAX,
+            max_headers: usize::MAX,
+            max_headers_size: usize::MAX,
+            max_body_size: u64::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_request_line_length: DEFAULT_MAX_REQUEST_LINE_LENGTH,
+            max_header_line_length: DEFAULT_MAX_HEADER_LINE_LENGTH,
+            max_headers: DEFAULT_MAX_HEADERS,
+            max_headers_size: DEFAULT_MAX_HEADERS_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
@@ -1,14 +1,24 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::fmt;
 use std::fmt::Debug;
 use std::io::{self, BufReader, BufWriter};
 use std::io::{prelude::*, Cursor};
+use std::mem;
+use std::rc::Rc;
 use std::str::FromStr;
 
+/// Contains the helpers to evaluate conditional requests.
+pub mod conditional;
 /// Contains the definition of the errors used in the Http module.
 pub mod errors;
 /// Contains all the types needed to read and write Http headers.
 pub mod headers;
+/// Contains the content negotiation helpers.
+pub mod negotiation;
+/// Contains the helpers to answer range requests.
+pub mod range;
 /// Contains the definition of all the standard Http status code.
 pub mod status;
 pub use self::status::StatusCode;
@@ -16,6 +26,28 @@ pub use self::status::StatusCode;
 mod version;
 pub use self::version::Version;
 
+mod chunked;
+use chunked::{ChunkedReader, Trailers};
+
+mod uri;
+pub use self::uri::Uri;
+
+mod limits;
+pub use self::limits::*;
+
+mod expect;
+use expect::ContinueReader;
+
+mod date;
+pub(crate) use self::date::http_date_now;
+pub use self::date::{format_http_date, parse_http_date};
+
+mod cookie;
+pub use self::cookie::{Cookie, SameSite};
+
+mod multipart;
+pub use self::multipart::*;
+
 use headers::*;
 use mime::Mime;
 
@@ -37,13 +69,16 @@ pub struct Request {
     /// The [``HttpMethod``] of the request.
     pub method: HttpMethod,
     /// The uri of the request.
-    pub uri: String,
-    /// The version of the request.
-    pub version: String,
+    pub uri: Uri,
+    /// The [``Version``] of the request.
+    pub version: Version,
     /// The headers of the request.
     pub headers: Headers,
     /// The body of the request, if any.
     pub body: Option<Body>,
+    /// The values captured by the parameters and wildcards of the route that
+    /// matched the request, indexed by their names, see [`crate::router::Router::add`].
+    pub params: HashMap<String, String>,
 }
 
 impl Request {
@@ -61,25 +96,87 @@ impl Request {
     does not conform to the spec: https://datatracker.ietf.org/doc/html/rfc7230.
     */
     pub fn read_from<T: io::Read + 'static>(from: T) -> HttpResult<Request> {
+        Self::read_from_with_limits(from, &Limits::default())
+    }
+
+    /**
+    Reads a request from an HTTP message in a type implementing [`io::Read`]
+    enforcing the given [`Limits`].
+
+    # Examples
+
+    ```
+    use std::io::Cursor;
+    use wruster::http::{Limits, Request};
+    use wruster::http::errors::HttpError;
+
+    let limits = Limits {
+        max_request_line_length: 16,
+        ..Limits::default()
+    };
+    let from = Cursor::new("GET /a/very/long/path HTTP/1.1\r\n\r\n");
+    let err = Request::read_from_with_limits(from, &limits).unwrap_err();
+    assert_eq!(err, HttpError::RequestLineTooLong);
+    ```
+
+    # Errors
+
+    Returns a [`HttpError::RequestLineTooLong`], a
+    [`HttpError::HeadersTooLarge`] or a [`HttpError::BodyTooLarge`] if the
+    corresponding limit is exceeded, or a [``HttpError``] if there is any
+    problem reading from ``from`` or the message does not conform to the spec.
+    */
+    pub fn read_from_with_limits<T: io::Read + 'static>(
+        from: T,
+        limits: &Limits,
+    ) -> HttpResult<Request> {
+        Self::read_from_buffered(io::BufReader::new(from), limits)
+    }
+
+    /**
+    Reads a request from a type implementing [`io::BufRead`] enforcing the
+    given [`Limits`]. The request never reads past the end of the message, so
+    when the body of the request has been completely read, the reader is
+    positioned at the start of the next message, if any. This allows reading
+    pipelined requests from the same buffered reader.
+
+    # Examples
+
+    ```
+    use std::io::{BufReader, Cursor};
+    use wruster::http::{Limits, Request};
+
+    let from = Cursor::new("GET /a HTTP/1.1\r\n\r\n");
+    let reader = BufReader::new(from);
+    let request = Request::read_from_buffered(reader, &Limits::default()).unwrap();
+    assert_eq!(request.uri.path(), "/a");
+    ```
+
+    # Errors
+
+    Returns the same errors as [`Request::read_from_with_limits`].
+    */
+    pub fn read_from_buffered<T: io::BufRead + 'static>(
+        from: T,
+        limits: &Limits,
+    ) -> HttpResult<Request> {
         debug!("parsing request");
-        let mut reader = io::BufReader::new(from);
-        let request_line = match HttpRequestLine::read_from(&mut reader) {
-            Ok(request) => request,
-            Err(err) => return Err(err),
-        };
+        let mut reader = from;
+        let request_line = HttpRequestLine::read_from(&mut reader, limits)?;
         debug!("request line parsed: {:?}", request_line);
-        let headers = Headers::read_from(&mut reader)?;
+        let headers = Headers::read_from_with_limits(&mut reader, limits)?;
         debug!("headers parsed: {:?}", headers);
 
-        let body = Body::read_from(reader, &headers)?;
+        let body = Body::read_from_with_limits(reader, &headers, limits)?;
         debug!("body read: {:?}", body);
 
         let request = Request {
             method: request_line.method,
-            uri: request_line.uri,
+            uri: Uri::parse(&request_line.uri)?,
             version: request_line.version,
             headers,
             body,
+            params: HashMap::new(),
         };
         debug!("request parsed: {:?}", request);
         Ok(request)
@@ -116,6 +213,9 @@ impl Request {
 
     /**
     Writes a [``Request``] to a type implementing the [``io::Write``] trait.
+    If the body is chunked, see [`Body::chunked`], the ``Transfer-Encoding``
+    header is added, together with the ``Trailer`` header if the body has
+    trailers.
 
     # Examples
 
@@ -127,24 +227,194 @@ impl Request {
     to the ``to`` paramerer.
     */
     pub fn write<T: io::Write>(mut self, to: &mut T) -> HttpResult<()> {
+        self.write_head(to)?;
+        match self.body {
+            Some(mut body) => body.write(to),
+            None => Ok(()),
+        }
+    }
+
+    /**
+    Writes the request line and the headers of the [``Request``] to a type
+    implementing the [``io::Write``] trait, but not its body. It can be used
+    to wait for a ``100 Continue`` interim response before sending the body of
+    a request that contains the ``Expect: 100-continue`` header.
+
+    # Errors
+
+    This function will return an error if there is any error writing
+    to the ``to`` paramerer.
+    */
+    pub fn write_head<T: io::Write>(&mut self, to: &mut T) -> HttpResult<()> {
         let mut start_line = HttpRequestLine {
-            method: self.method,
-            uri: self.uri,
+            method: self.method.clone(),
+            uri: self.uri.to_string(),
             version: self.version,
         };
 
         start_line.write(to)?;
-        if self.body.is_none() {
-            self.headers.add(Header {
-                name: String::from("Content-Length"),
-                value: String::from("0"),
-            })
-        }
-        self.headers.write(to)?;
-        match self.body {
-            Some(mut body) => body.write(to).map_err(HttpError::from),
-            None => Ok(()),
+        add_framing_headers(&mut self.headers, self.body.as_ref());
+        self.headers.write(to)
+    }
+
+    /**
+    Returns true if the request contains the ``Expect: 100-continue`` header:
+    <https://datatracker.ietf.org/doc/html/rfc7231#section-5.1.1>. The server
+    sends the ``100 Continue`` interim response when the handler of the
+    request starts reading the body, so a handler can reject the request,
+    e.g. with a 413 or 417 status code, without the client sending the body,
+    by returning a response without reading it.
+
+    # Examples
+
+    ```
+    use wruster::http::Request;
+
+    let str_req = "POST / HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 4\r\n\r\n";
+    let req = Request::read_from_str(str_req).unwrap();
+    assert!(req.expects_continue());
+    ```
+    */
+    pub fn expects_continue(&self) -> bool {
+        self.version == Version::HTTP1_1
+            && self
+                .headers
+                .get_first("Expect")
+                .map(|expect| expect.trim().eq_ignore_ascii_case("100-continue"))
+                .unwrap_or(false)
+    }
+
+    /**
+    Returns the cookies sent in the ``Cookie`` header of the request indexed
+    by their names, see: <https://datatracker.ietf.org/doc/html/rfc6265#section-5.4>.
+    If a cookie is sent more than once, only its first value is returned.
+
+    # Examples
+
+    ```
+    use wruster::http::Request;
+
+    let str_req = "GET / HTTP/1.1\r\nCookie: session=38afes7a8; theme=\"dark\"\r\n\r\n";
+    let req = Request::read_from_str(str_req).unwrap();
+    let cookies = req.cookies();
+    assert_eq!(cookies.get("session").map(String::as_str), Some("38afes7a8"));
+    assert_eq!(cookies.get("theme").map(String::as_str), Some("dark"));
+    ```
+    */
+    pub fn cookies(&self) -> HashMap<String, String> {
+        cookie::parse_cookies(&self.headers)
+    }
+
+    /**
+    Returns the decoded parameters of the query string of the request,
+    indexed by name, see [`Uri::query_params`].
+    */
+    pub fn query(&self) -> &HashMap<String, Vec<String>> {
+        self.uri.query_params()
+    }
+
+    /**
+    Reads the body of the request and decodes it as an
+    ``application/x-www-form-urlencoded`` form into a map that contains all
+    the values of each field. A request without body returns an empty map.
+
+    # Examples
+
+    ```
+    use wruster::http::Request;
+
+    let str_req = "POST / HTTP/1.1\r\n\
+        Content-Type: application/x-www-form-urlencoded\r\n\
+        Content-Length: 29\r\n\r\n\
+        name=J%C3%BAlia+A&tag=a&tag=b";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    let form = req.form().unwrap();
+    assert_eq!(form["name"], vec!["Júlia A"]);
+    assert_eq!(form["tag"], vec!["a", "b"]);
+    ```
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the body has a content type
+    other than ``application/x-www-form-urlencoded`` or is not correctly
+    encoded, and a [`HttpError`] if there is any problem reading the body.
+    */
+    pub fn form(&mut self) -> HttpResult<HashMap<String, Vec<String>>> {
+        let content = self.form_content()?;
+        let content = String::from_utf8(content)
+            .map_err(|_| InvalidRequest(String::from("invalid urlencoded form")))?;
+        uri::parse_query(&content)
+    }
+
+    /**
+    Reads the body of the request and deserializes it, as an
+    ``application/x-www-form-urlencoded`` form, into a ``T``.
+
+    # Examples
+
+    ```
+    use serde::Deserialize;
+    use wruster::http::Request;
+
+    #[derive(Deserialize)]
+    struct Login {
+        user: String,
+        remember: bool,
+    }
+
+    let str_req = "POST / HTTP/1.1\r\n\
+        Content-Type: application/x-www-form-urlencoded\r\n\
+        Content-Length: 29\r\n\r\n\
+        user=J%C3%BAlia&remember=true";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    let login: Login = req.form_as().unwrap();
+    assert_eq!(login.user, "Júlia");
+    assert!(login.remember);
+    ```
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the body is not a form or
+    can't be deserialized into a ``T``, and a [`HttpError`] if there is any
+    problem reading the body.
+    */
+    #[cfg(feature = "serde")]
+    pub fn form_as<T: serde::de::DeserializeOwned>(&mut self) -> HttpResult<T> {
+        let content = self.form_content()?;
+        serde_urlencoded::from_bytes(&content)
+            .map_err(|err| InvalidRequest(format!("invalid urlencoded form: {}", err)))
+    }
+
+    /**
+    Deserializes the query string of the request into a ``T``, a request
+    without query string is deserialized as an empty one.
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the query string can't be
+    deserialized into a ``T``.
+    */
+    #[cfg(feature = "serde")]
+    pub fn query_as<T: serde::de::DeserializeOwned>(&self) -> HttpResult<T> {
+        let query = self.uri.query().unwrap_or("");
+        serde_urlencoded::from_str(query)
+            .map_err(|err| InvalidRequest(format!("invalid query string: {}", err)))
+    }
+
+    fn form_content(&mut self) -> HttpResult<Vec<u8>> {
+        let body = match self.body.as_mut() {
+            None => return Ok(Vec::new()),
+            Some(body) => body,
+        };
+        if let Some(content_type) = body.content_type.as_ref() {
+            if content_type.essence_str() != mime::APPLICATION_WWW_FORM_URLENCODED.essence_str() {
+                let msg = format!("unexpected form content type {}", content_type);
+                return Err(InvalidRequest(msg));
+            }
         }
+        let mut content = Vec::new();
+        body.read_to_end(&mut content).map_err(HttpError::from)?;
+        Ok(content)
     }
 
     /**
@@ -158,24 +428,19 @@ impl Request {
     */
     pub fn from_body(body: Body, method: HttpMethod, path: &str) -> Self {
         let mut headers = Headers::new();
-        if let Some(content_type) = body.content_type.clone() {
-            headers.add(Header {
-                name: "Content-Type".to_string(),
-                value: content_type.to_string(),
-            });
+        if let Some(content_type) = body.content_type.as_ref() {
+            headers.set_content_type(content_type);
         }
         if body.content_length != 0 {
-            headers.add(Header {
-                name: "Content-Length".to_string(),
-                value: body.content_length.to_string(),
-            });
+            headers.set_content_length(body.content_length);
         }
         Request {
             body: Some(body),
             headers,
             method,
-            uri: path.to_string(),
-            version: Version::HTTP1_1.to_string(),
+            uri: Uri::unchecked(path),
+            version: Version::HTTP1_1,
+            params: HashMap::new(),
         }
     }
 
@@ -188,19 +453,18 @@ impl Request {
 
     */
     pub fn is_connection_persistent(&self) -> bool {
-        let value = match self.headers.get("Connection") {
-            None => "".to_string(),
-            Some(values) => values[0].to_lowercase(),
-        };
-        if value == "close" {
+        let connection = self.headers.connection();
+        if connection.iter().any(|option| option == "close") {
             return false;
         }
 
-        if self.version == "HTTP/1.1" || self.version == "HTTP/2" {
+        if self.version == Version::HTTP1_1 || self.version == Version::HTTP2 {
             return true;
         };
 
-        if self.version == "HTTP/1.0" && value == "keep-alive" {
+        if self.version == Version::HTTP1_0
+            && connection.iter().any(|option| option == "keep-alive")
+        {
             return true;
         };
         false
@@ -224,8 +488,9 @@ where
             body: Some(body),
             headers: Headers::new(),
             method,
-            uri: url,
-            version: Version::HTTP1_1.to_string(),
+            uri: Uri::unchecked(&url),
+            version: Version::HTTP1_1,
+            params: HashMap::new(),
         }
     }
 }
@@ -234,13 +499,21 @@ where
 struct HttpRequestLine {
     method: HttpMethod,
     uri: String,
-    version: String,
+    version: Version,
 }
 
 impl HttpRequestLine {
-    fn read_from<T: io::Read>(from: &mut io::BufReader<T>) -> Result<HttpRequestLine, HttpError> {
+    fn read_from<T: io::BufRead>(
+        from: &mut T,
+        limits: &Limits,
+    ) -> Result<HttpRequestLine, HttpError> {
         // Request-Line   = Method SP Request-URI SP HTTP-Version CRLF
         // https://www.w3.org/Protocols/rfc2616/rfc2616-sec5.html
+        let max_len = limits.max_request_line_length as u64;
+        let mut from = from.take(max_len);
+        let exceeded = |part: &[u8], delimiter: u8, from: &io::Take<_>| {
+            part.last() != Some(&delimiter) && from.limit() == 0
+        };
 
         let mut method = Vec::new();
         from.read_until(b' ', &mut method)
@@ -248,6 +521,9 @@ impl HttpRequestLine {
         if method.is_empty() {
             return Err(ConnectionClosed);
         }
+        if exceeded(&method, b' ', &from) {
+            return Err(HttpError::RequestLineTooLong);
+        }
         if method.len() < 2 {
             let msg = format!("invalid request line {:?}", method);
             return Err(InvalidRequest(msg));
@@ -262,6 +538,9 @@ impl HttpRequestLine {
         if let Err(err) = from.read_until(b' ', &mut uri) {
             return Err(Unknown(err.to_string()));
         };
+        if exceeded(&uri, b' ', &from) {
+            return Err(HttpError::RequestLineTooLong);
+        }
         if uri.len() < 2 {
             return Err(InvalidRequest("invalid request line".to_string()));
         };
@@ -271,6 +550,9 @@ impl HttpRequestLine {
         let mut version = Vec::new();
         from.read_until(b'\n', &mut version)
             .map_err(HttpError::from)?;
+        if exceeded(&version, b'\n', &from) {
+            return Err(HttpError::RequestLineTooLong);
+        }
         if version.len() < 3 {
             return Err(InvalidRequest("invalid request line".to_string()));
         };
@@ -279,11 +561,20 @@ impl HttpRequestLine {
             return Err(InvalidRequest("invalid request line".to_string()));
         }
         let version = String::from_utf8_lossy(&version[..version.len() - 2]);
+        if validate_version(&version).is_err() {
+            let msg = format!("invalid http version {}", version);
+            return Err(InvalidRequest(msg));
+        }
+        // Only the versions using the HTTP/1 message syntax can be read.
+        let version = match Version::from_str(&version) {
+            Ok(version @ (Version::HTTP1_0 | Version::HTTP1_1)) => version,
+            _ => return Err(HttpError::VersionNotSupported(version.to_string())),
+        };
 
         Ok(HttpRequestLine {
             method,
             uri: String::from(uri),
-            version: String::from(version),
+            version,
         })
     }
 
@@ -306,6 +597,7 @@ pub struct Body {
     pub content: Box<dyn Read>,
 
     bytes_read: u64,
+    trailers: Option<Trailers>,
 }
 
 impl Body {
@@ -338,11 +630,75 @@ impl Body {
             content_length,
             content,
             bytes_read,
+            trailers: None,
+        }
+    }
+
+    /**
+    Creates a new Body of unknown length given a Reader over the content of
+    the body and the content type. The body will be written using the
+    ``chunked`` transfer coding:
+    <https://datatracker.ietf.org/doc/html/rfc7230#section-4.1>.
+
+    # Examples
+
+    ```
+    use std::io::Cursor;
+    use wruster::http::Body;
+
+    let content = "content";
+    let mut body = Body::chunked(Some(mime::TEXT_PLAIN), Box::new(Cursor::new(content)));
+    let mut to: Vec<u8> = Vec::new();
+    body.write(&mut to).unwrap();
+    let got_content = String::from_utf8(to).unwrap();
+    assert_eq!("7\r\ncontent\r\n0\r\n\r\n", &got_content)
+    ```
+    */
+    pub fn chunked(content_type: Option<mime::Mime>, content: Box<dyn Read>) -> Body {
+        Body {
+            content_type,
+            content_length: 0,
+            content,
+            bytes_read: 0,
+            trailers: Some(Rc::new(RefCell::new(None))),
+        }
+    }
+
+    /**
+    Sets the trailer fields to send after the last chunk of a body created
+    with [`Body::chunked`]. The trailers are ignored if the body is not
+    chunked.
+
+    # Examples
+
+    ```
+    use std::io::Cursor;
+    use wruster::http::Body;
+    use wruster::http::headers::{Header, Headers};
+
+    let mut body = Body::chunked(None, Box::new(Cursor::new("content")));
+    let mut trailers = Headers::new();
+    trailers.add(Header {
+        name: String::from("Checksum"),
+        value: String::from("abc"),
+    });
+    body.set_trailers(trailers);
+    let mut to: Vec<u8> = Vec::new();
+    body.write(&mut to).unwrap();
+    let got_content = String::from_utf8(to).unwrap();
+    assert_eq!("7\r\ncontent\r\n0\r\nChecksum: abc\r\n\r\n", &got_content)
+    ```
+    */
+    pub fn set_trailers(&mut self, trailers: Headers) {
+        if let Some(current) = self.trailers.as_ref() {
+            *current.borrow_mut() = Some(trailers);
         }
     }
 
     /**
     Writes the content of body to a type implementing the [``io::Write``] trait.
+    If the body is chunked, the content is written using the ``chunked``
+    transfer coding followed by the trailers, if any.
 
     # Examples
 
@@ -369,6 +725,11 @@ impl Body {
     */
     pub fn write<T: io::Write>(&mut self, to: &mut T) -> HttpResult<()> {
         let src = &mut self.content;
+        if let Some(trailers) = self.trailers.as_ref() {
+            let trailers = trailers.borrow();
+            return chunked::write_chunked(src, to, trailers.as_ref())
+                .map_err(|err| HttpError::Unknown(err.to_string()));
+        }
 
         // When the content to write is large (>2MB's) the io::copy function
         // uses buffers that are too short, to avoid that we use this technique:
@@ -389,39 +750,106 @@ impl Body {
     }
 
     /**
-
     Reads the body of a Http message given the Headers of the message and
-    a type implementing the [`io::Read`] trait that contains content of the
+    a type implementing the [`io::BufRead`] trait that contains content of the
     body. The method assumes that the content and the headers follow the spec
-    https://datatracker.ietf.org/doc/html/rfc7230#page-27. By now, the method only
-    supports the ``Content-Length`` header and not ``Transfer-Encoding`` header.
+    https://datatracker.ietf.org/doc/html/rfc7230#section-3.3. The
+    ``Transfer-Encoding`` header takes precedence over the ``Content-Length``
+    header, and only the ``identity`` and ``chunked`` transfer codings are
+    supported.
 
     # Examples
 
-    TODO
+    ```
+    use std::io::{Cursor, Read};
+    use wruster::http::Body;
+    use wruster::http::headers::{Header, Headers};
+
+    let mut headers = Headers::new();
+    headers.add(Header {
+        name: String::from("Transfer-Encoding"),
+        value: String::from("chunked"),
+    });
+    let from = Cursor::new("4\r\ntest\r\n0\r\n\r\n");
+    let mut body = Body::read_from(from, &headers).unwrap().unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "test");
+    ```
 
     # Errors
 
     This function will return an error if the ``Headers`` parameter contains a
-    ``Transfer-Encoding`` header or if it contains more that one value a ``Content-Length``
-    header.
+    ``Transfer-Encoding`` header with a not supported transfer coding, if the
+    ``chunked`` transfer coding is not the last one applied or if it contains
+    more than one value in the ``Content-Length`` header.
     */
-    pub fn read_from<T: io::Read + 'static>(
+    pub fn read_from<T: io::BufRead + 'static>(
         from: T,
         headers: &Headers,
     ) -> Result<Option<Body>, HttpError> {
-        if let Some(encoding) = headers.get("Transfer-Encoding") {
-            // Transfer-Encoding entity is not supported.
-            if encoding.len() != 1 {
-                let msg = "invalid Transfer-Encoding header".to_string();
-                return Err(Unknown(msg));
-            }
-            if encoding[0] != "identity" {
-                let msg = format!("Transfer-Encoding: {} is not supported", encoding[0]);
-                return Err(Unknown(msg));
+        Self::read_from_with_limits(from, headers, &Limits::unlimited())
+    }
+
+    /**
+    Reads a body in the same way as [`Body::read_from`] but enforcing the
+    maximum body size defined in the ``limits`` parameter. The limit is
+    checked against the ``Content-Length`` header when the body is read
+    and, for the bodies encoded using the ``chunked`` transfer coding, while
+    the content of the body is read.
+
+    # Errors
+
+    Returns a [`HttpError::BodyTooLarge`] if the ``Content-Length`` of the body
+    exceeds the limit, or any of the errors returned by [`Body::read_from`].
+    Reading the content of a chunked body that exceeds the limit returns an
+    [`io::Error`] of kind [`io::ErrorKind::InvalidData`] that is converted to
+    a [`HttpError::BodyTooLarge`] by [`HttpError::from`].
+    */
+    pub fn read_from_with_limits<T: io::BufRead + 'static>(
+        from: T,
+        headers: &Headers,
+        limits: &Limits,
+    ) -> Result<Option<Body>, HttpError> {
+        let chunked = match headers.get("Transfer-Encoding") {
+            None => false,
+            Some(encodings) => Self::parse_transfer_encoding(encodings)?,
+        };
+
+        let content_type = match headers.get("Content-Type") {
+            None => None,
+            Some(types) => {
+                if types.is_empty() {
+                    let msg = format!("invalid Content-Type header, {:?}", types);
+                    return Err(Unknown(msg));
+                };
+                let mtype: mime::Mime = match types[0].parse() {
+                    Ok(t) => t,
+                    Err(err) => {
+                        let msg = format!("invalid Content-Type header, {:?}, {}", types, err);
+                        return Err(Unknown(msg));
+                    }
+                };
+                Some(mtype)
             }
         };
 
+        // When the Transfer-Encoding header is present, the Content-Length
+        // header must be ignored:
+        // https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3.
+        if chunked {
+            let reader = ChunkedReader::new(from, *limits);
+            let trailers = reader.trailers();
+            let body = Body {
+                content: Box::new(reader),
+                content_type,
+                content_length: 0,
+                bytes_read: 0,
+                trailers: Some(trailers),
+            };
+            return Ok(Some(body));
+        }
+
         let len = match headers.get("Content-Length") {
             None => return Ok(None),
             Some(lengths) => {
@@ -443,23 +871,10 @@ impl Body {
         if len == 0 {
             return Ok(None);
         }
-        let content_type = match headers.get("Content-Type") {
-            None => None,
-            Some(types) => {
-                if types.is_empty() {
-                    let msg = format!("invalid Content-Type header, {:?}", types);
-                    return Err(Unknown(msg));
-                };
-                let mtype: mime::Mime = match types[0].parse() {
-                    Ok(t) => t,
-                    Err(err) => {
-                        let msg = format!("invalid Content-Type header, {:?}, {}", types, err);
-                        return Err(Unknown(msg));
-                    }
-                };
-                Some(mtype)
-            }
-        };
+        if len as u64 > limits.max_body_size {
+            debug!("maximum body size exceeded, content length: {}", len);
+            return Err(HttpError::BodyTooLarge);
+        }
         let c = from.take(len as u64);
         let content = Box::new(c);
         let body = Body {
@@ -467,14 +882,67 @@ impl Body {
             content_type,
             content_length: len as u64,
             bytes_read: 0,
+            trailers: None,
         };
         Ok(Some(body))
     }
 
+    fn parse_transfer_encoding(encodings: &[String]) -> Result<bool, HttpError> {
+        // Transfer-Encoding = 1#transfer-coding
+        // https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.1
+        let encodings = encodings
+            .iter()
+            .flat_map(|encoding| encoding.split(','))
+            .map(|encoding| encoding.trim().to_lowercase())
+            .filter(|encoding| !encoding.is_empty())
+            .collect::<Vec<String>>();
+        if encodings.is_empty() {
+            let msg = "invalid Transfer-Encoding header".to_string();
+            return Err(Unknown(msg));
+        }
+        let mut chunked = false;
+        for (i, encoding) in encodings.iter().enumerate() {
+            match encoding.as_str() {
+                "identity" => continue,
+                "chunked" if i == encodings.len() - 1 => chunked = true,
+                "chunked" => {
+                    let msg = "chunked must be the last Transfer-Encoding applied".to_string();
+                    return Err(Unknown(msg));
+                }
+                encoding => {
+                    let msg = format!("Transfer-Encoding: {} is not supported", encoding);
+                    return Err(Unknown(msg));
+                }
+            }
+        }
+        Ok(chunked)
+    }
+
+    /**
+    Makes the body send a ``100 Continue`` interim response to ``to`` the first
+    time its content is read. Returns a flag that is set to true when the
+    interim response has been sent.
+    */
+    pub(crate) fn expect_continue<W: io::Write + 'static>(&mut self, to: W) -> Rc<Cell<bool>> {
+        let content = std::mem::replace(&mut self.content, Box::new(io::empty()));
+        let reader = ContinueReader::new(content, to);
+        let sent = reader.sent();
+        self.content = Box::new(reader);
+        sent
+    }
+
     /**
     Ensures the content length specified in the body is read from the underlying reader.
+    If the body is encoded using the ``chunked`` transfer coding, the body is
+    read until the last chunk and the trailer fields.
     */
     pub fn ensure_read(&mut self) -> Result<(), HttpError> {
+        if self.trailers.is_some() {
+            return match io::copy(&mut self.content, &mut io::sink()) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(HttpError::from(err)),
+            };
+        }
         if self.bytes_read == self.content_length {
             return Ok(());
         }
@@ -485,6 +953,42 @@ impl Body {
         }
     }
 
+    /**
+    Returns the trailer fields of a body encoded using the ``chunked`` transfer
+    coding. The trailers are only available after the content of the
+    body has been completely read, e.g. after calling [`Body::ensure_read`].
+
+    # Examples
+
+    ```
+    use std::io::Cursor;
+    use wruster::http::Body;
+    use wruster::http::headers::{Header, Headers};
+
+    let mut headers = Headers::new();
+    headers.add(Header {
+        name: String::from("Transfer-Encoding"),
+        value: String::from("chunked"),
+    });
+    let from = Cursor::new("4\r\ntest\r\n0\r\nExpires: never\r\n\r\n");
+    let mut body = Body::read_from(from, &headers).unwrap().unwrap();
+    body.ensure_read().unwrap();
+    let trailers = body.trailers().unwrap();
+    assert_eq!(trailers.get("Expires"), Some(&vec![String::from("never")]));
+    ```
+    */
+    pub fn trailers(&self) -> Option<Headers> {
+        let trailers = self.trailers.as_ref()?;
+        let trailers = trailers.borrow();
+        trailers.clone()
+    }
+
+    /// Returns true if the body is encoded using the ``chunked`` transfer
+    /// coding.
+    pub fn is_chunked(&self) -> bool {
+        self.trailers.is_some()
+    }
+
     /**
     Creates a Body from value of a type implementing the trait [`IntoBody`]
 
@@ -516,12 +1020,50 @@ impl fmt::Debug for Body {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "content-type: {:?}, content-length: {}, content: ....",
-            self.content_type, self.content_length
+            "content-type: {:?}, content-length: {}, chunked: {}, content: ....",
+            self.content_type,
+            self.content_length,
+            self.is_chunked()
         )
     }
 }
 
+// Adds the headers needed by the receiver of a message to know where the
+// body of the message ends:
+// https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3.
+fn add_framing_headers(headers: &mut Headers, body: Option<&Body>) {
+    let body = match body {
+        None => {
+            if !headers.contains("Content-Length") {
+                headers.set_content_length(0);
+            }
+            return;
+        }
+        Some(body) => body,
+    };
+    let trailers = match body.trailers.as_ref() {
+        None => return,
+        Some(trailers) => trailers.borrow(),
+    };
+    // A message with a Transfer-Encoding must not have a Content-Length:
+    // https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.2.
+    headers.remove("Content-Length");
+    if !headers.contains("Transfer-Encoding") {
+        headers.set(Header::new("Transfer-Encoding", "chunked"));
+    }
+    let trailers = match trailers.as_ref() {
+        None => return,
+        Some(trailers) => trailers,
+    };
+    let names = trailers
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<String>>();
+    if !names.is_empty() && !headers.contains("Trailer") {
+        headers.set(Header::new("Trailer", &names.join(", ")));
+    }
+}
+
 /**
 Used to convert an immutable reference to a Body.
 
@@ -565,6 +1107,9 @@ impl From<&str> for Body {
 /// Represents a Http Response.
 #[derive(Debug)]
 pub struct Response {
+    /// The [``Version``] of the response. The server sets it to the version
+    /// of the request before writing the response.
+    pub version: Version,
     /// The http [``StatusCode``] of the response.
     pub status: StatusCode,
     /// The [``Headers``] of the response.
@@ -576,13 +1121,19 @@ pub struct Response {
 impl Response {
     /**
     Writes a [``Response``] to a type implementing the [``io::Write``] trait.
+    If the body is chunked, see [`Body::chunked`], the ``Transfer-Encoding``
+    header is added, together with the ``Trailer`` header if the body has
+    trailers. HTTP/1.0 doesn't support the ``chunked`` transfer coding, so if
+    the version of the response is HTTP/1.0, the content of a chunked body is
+    written as is and the end of the body is signaled by closing the
+    connection.
 
     # Examples
 
     ```
     use std::io::Cursor;
     use wruster::http::headers::{Header, Headers};
-    use wruster::http::{Body, Response, StatusCode};
+    use wruster::http::{Body, Response, StatusCode, Version};
 
     let content = "#wruster";
     let body = Body::new(
@@ -597,6 +1148,7 @@ impl Response {
     value: String::from("8"),
     });
     let mut response = Response {
+    version: Version::HTTP1_1,
     status: StatusCode::OK,
     headers: headers,
     body: Some(body),
@@ -612,23 +1164,114 @@ impl Response {
     to the ``to`` paramerer.
     */
     pub fn write<T: io::Write>(&mut self, to: &mut T) -> HttpResult<()> {
+        self.write_head(to)?;
+        match self.body.as_mut() {
+            Some(body) => body.write(to),
+            None => Ok(()),
+        }
+    }
+
+    /**
+    Writes the status line and the headers of the response to a type
+    implementing [`io::Write`], but not its body. The headers are the same
+    ones that [`Response::write`] would send, including the ``Content-Length``
+    of the body, so this is the method to use for answering ``HEAD`` requests:
+    <https://httpwg.org/specs/rfc7231.html#HEAD>.
+
+    # Examples
+
+    ```
+    use std::str::FromStr;
+    use wruster::http::Response;
+
+    let mut response = Response::from_str("content").unwrap();
+    let mut to: Vec<u8> = Vec::new();
+    response.write_head(&mut to).unwrap();
+    let head = String::from_utf8(to).unwrap();
+    assert!(head.contains("Content-Length: 7"));
+    assert!(!head.contains("content"));
+    ```
+
+    # Errors
+
+    This function will return an error if there is any error writing
+    to the ``to`` paramerer.
+    */
+    pub fn write_head<T: io::Write>(&mut self, to: &mut T) -> HttpResult<()> {
         let mut start_line = HttpResponseLine {
-            http_version: Version::HTTP1_1.to_string(),
+            http_version: self.version,
             status_code: self.status.clone(),
             reason_phrase: self.status.clone().into(),
         };
         start_line.write(to)?;
-        if self.body.is_none() {
-            self.headers.add(Header {
-                name: String::from("Content-Length"),
-                value: String::from("0"),
-            })
+        if self.is_close_delimited() {
+            // The body is written without the chunked transfer coding, its
+            // trailers are discarded.
+            if let Some(body) = self.body.as_mut() {
+                body.trailers = None;
+            }
+            self.headers.remove("Trailer");
+            self.headers.remove("Transfer-Encoding");
+            return self.headers.write(to);
         }
-        self.headers.write(to)?;
-        match self.body.as_mut() {
-            Some(body) => body.write(to).map_err(HttpError::from),
-            None => Ok(()),
+        // A 304 response never has a body, its Content-Length, if any, is the
+        // one of the representation it refers to.
+        if self.status != StatusCode::NotModified {
+            add_framing_headers(&mut self.headers, self.body.as_ref());
         }
+        // A response body with a known length must always be delimited by
+        // a Content-Length header, otherwise the client can't tell where the
+        // response ends.
+        if let Some(body) = self.body.as_ref() {
+            if !body.is_chunked() && !self.headers.contains("Content-Length") {
+                self.headers.set_content_length(body.content_length);
+            }
+        }
+        self.headers.write(to)
+    }
+
+    /**
+    Returns true if the end of the body of the response can only be signaled
+    by closing the connection, that is: the body is chunked and the version of
+    the response is HTTP/1.0.
+    */
+    pub fn is_close_delimited(&self) -> bool {
+        self.version == Version::HTTP1_0
+            && self
+                .body
+                .as_ref()
+                .map(|body| body.is_chunked())
+                .unwrap_or(false)
+    }
+
+    /**
+    Adds a ``Set-Cookie`` header for the given [`Cookie`] to the response.
+    Each cookie is sent in its own ``Set-Cookie`` header.
+
+    # Examples
+
+    ```
+    use wruster::http::{Cookie, Response, StatusCode};
+
+    let mut response = Response::from_status(StatusCode::OK);
+    response.add_cookie(&Cookie::new("a", "1")).unwrap();
+    response.add_cookie(&Cookie::new("b", "2").http_only()).unwrap();
+    assert_eq!(
+        response.headers.get("Set-Cookie"),
+        Some(&vec![String::from("a=1"), String::from("b=2; HttpOnly")])
+    );
+    ```
+
+    # Errors
+
+    Returns an [`HttpError::InvalidCookie`] if the cookie is not valid, see
+    [`Cookie::validate`].
+    */
+    pub fn add_cookie(&mut self, cookie: &Cookie) -> HttpResult<()> {
+        cookie.validate()?;
+        self.headers
+            .add(Header::new("Set-Cookie", &cookie.to_string()));
+        Ok(())
     }
 
     /// Creates a Request with the given http [``StatusCode``].
@@ -643,6 +1286,7 @@ impl Response {
     pub fn from_status(status: StatusCode) -> Response {
         let headers = Headers::new();
         Response {
+            version: Version::HTTP1_1,
             status,
             headers,
             body: None,
@@ -657,17 +1301,38 @@ impl Response {
         let mut headers = Headers::new();
         let reader = BufReader::new(content.take(length));
         let content = Box::new(reader);
-        headers.add(Header {
-            name: String::from("Content-Length"),
-            value: length.to_string(),
-        });
-        headers.add(Header {
-            name: String::from("Content-Type"),
-            value: mime.to_string(),
-        });
+        headers.set_content_length(length);
+        headers.set_content_type(&mime);
 
         let body = Body::new(Some(mime), length, content);
         Response {
+            version: Version::HTTP1_1,
+            status: StatusCode::OK,
+            headers,
+            body: Some(body),
+        }
+    }
+
+    /// Creates a Response with the given content of unknown length and mime
+    /// type, the content is sent using the ``chunked`` transfer coding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use wruster::http::Response;
+    ///
+    /// let content = Cursor::new("a,b\n1,2\n");
+    /// let mut response = Response::from_chunked_content(content, mime::TEXT_CSV);
+    /// let mut to: Vec<u8> = Vec::new();
+    /// response.write(&mut to).unwrap();
+    /// ```
+    pub fn from_chunked_content<T: Read + 'static>(content: T, mime: Mime) -> Response {
+        let mut headers = Headers::new();
+        headers.set_content_type(&mime);
+        let body = Body::chunked(Some(mime), Box::new(content));
+        Response {
+            version: Version::HTTP1_1,
             status: StatusCode::OK,
             headers,
             body: Some(body),
@@ -708,6 +1373,7 @@ impl Response {
 
         let response = Response {
             body,
+            version: status_line.http_version,
             status: status_line.status_code,
             headers,
         };
@@ -721,6 +1387,7 @@ impl FromStr for Response {
     fn from_str(content: &str) -> Result<Response, Infallible> {
         let content = Vec::from(content);
         let resp = Response {
+            version: Version::HTTP1_1,
             status: StatusCode::OK,
             headers: Headers::new(),
             body: Some(Body::new(
@@ -735,7 +1402,7 @@ impl FromStr for Response {
 
 #[derive(Debug)]
 struct HttpResponseLine {
-    http_version: String,
+    http_version: Version,
     status_code: StatusCode,
     #[allow(dead_code)]
     reason_phrase: String,
@@ -757,9 +1424,11 @@ impl HttpResponseLine {
             return Err(ConnectionClosed);
         }
 
-        let mut http_version = String::from_utf8_lossy(&http_version).to_string();
-        http_version = http_version.trim_end().to_string();
-        Self::validate_version(&http_version)?;
+        let http_version = String::from_utf8_lossy(&http_version).to_string();
+        let http_version = http_version.trim_end();
+        validate_version(http_version)?;
+        let http_version = Version::from_str(http_version)
+            .map_err(|err| Unknown(format!("unsupported http version: {}", err.source())))?;
         let mut status_code = Vec::new();
         if let Err(err) = from.read_until(b' ', &mut status_code) {
             return Err(Unknown(err.to_string()));
@@ -790,37 +1459,6 @@ impl HttpResponseLine {
         })
     }
 
-    fn validate_version(version: &str) -> Result<(), HttpError> {
-        // https://www.w3.org/Protocols/rfc2616/rfc2616-sec3.html
-        // HTTP-Version   = "HTTP" "/" 1*DIGIT "." 1*DIGIT
-        let parts: Vec<&str> = version.split('/').collect();
-        if parts.len() != 2 {
-            return Err(Unknown(format!("invalid http version: {}", version)));
-        };
-        if parts[0] != "HTTP" {
-            return Err(Unknown(format!("invalid http version: {}", version)));
-        };
-
-        let digits_parts: Vec<&str> = parts[1].split('.').collect();
-        if digits_parts.len() != 2 {
-            return Err(Unknown(format!("invalid http version: {}", version)));
-        }
-
-        if let Err(error) = digits_parts[0].parse::<u8>() {
-            return Err(Unknown(format!(
-                "invalid http version: {} {}",
-                version, error
-            )));
-        }
-
-        if let Err(error) = digits_parts[1].parse::<u8>() {
-            return Err(Unknown(format!(
-                "invalid http version: {} {}",
-                version, error
-            )));
-        }
-        Ok(())
-    }
     pub fn write<T: io::Write>(&mut self, to: &mut T) -> HttpResult<()> {
         // status-line = HTTP-version SP status-code SP reason-phrase CRLF
         // https://datatracker.ietf.org/doc/html/rfc7230#section-3.1.2
@@ -833,12 +1471,63 @@ impl HttpResponseLine {
     }
 }
 
+fn validate_version(version: &str) -> Result<(), HttpError> {
+    // https://www.w3.org/Protocols/rfc2616/rfc2616-sec3.html
+    // HTTP-Version   = "HTTP" "/" 1*DIGIT "." 1*DIGIT
+    let parts: Vec<&str> = version.split('/').collect();
+    if parts.len() != 2 {
+        return Err(Unknown(format!("invalid http version: {}", version)));
+    };
+    if parts[0] != "HTTP" {
+        return Err(Unknown(format!("invalid http version: {}", version)));
+    };
+
+    let digits_parts: Vec<&str> = parts[1].split('.').collect();
+    if digits_parts.len() != 2 {
+        return Err(Unknown(format!("invalid http version: {}", version)));
+    }
+
+    if let Err(error) = digits_parts[0].parse::<u8>() {
+        return Err(Unknown(format!(
+            "invalid http version: {} {}",
+            version, error
+        )));
+    }
+
+    if let Err(error) = digits_parts[1].parse::<u8>() {
+        return Err(Unknown(format!(
+            "invalid http version: {} {}",
+            version, error
+        )));
+    }
+    Ok(())
+}
+
 #[allow(missing_docs)]
-/// Contains a variant per each Http Method.
-#[derive(Debug, Copy, Clone)]
-#[repr(u16)]
+/**
+Contains a variant per each standard Http Method, plus the
+[`HttpMethod::Extension`] variant for any other method, like the ones defined
+by WebDAV: ``PROPFIND``, ``MKCOL``, etc. See:
+<https://datatracker.ietf.org/doc/html/rfc7231#section-4.1>. Parsing a method
+with [`FromStr`] always returns the standard variant for the name of a
+standard method, so an [`HttpMethod::Extension`] must never be built with one
+of those names, as it wouldn't be equal to the standard variant.
+
+# Examples
+
+```
+use std::str::FromStr;
+use wruster::http::HttpMethod;
+
+assert_eq!(HttpMethod::from_str("GET").unwrap(), HttpMethod::GET);
+let method = HttpMethod::from_str("PROPFIND").unwrap();
+assert_eq!(method, HttpMethod::Extension(String::from("PROPFIND")));
+assert!(HttpMethod::from_str("BAD METHOD").is_err());
+```
+*/
+#[derive(Debug, Clone)]
 pub enum HttpMethod {
-    GET = 0,
+    GET,
     HEAD,
     POST,
     PUT,
@@ -847,19 +1536,75 @@ pub enum HttpMethod {
     OPTIONS,
     TRACE,
     PATCH,
+    /// A method not defined in the Http spec, it contains the name of the
+    /// method, that must be a valid token and is case-sensitive.
+    Extension(String),
 }
 
 impl HttpMethod {
-    /// The [``HttpMethod``] variants are represented using a [``u16``], this
-    /// method returns the variant with the highest value.
+    /// Contains all the standard [``HttpMethod``] variants sorted by their
+    /// index, see [`HttpMethod::index`].
+    pub const STANDARD: [HttpMethod; 9] = [
+        HttpMethod::GET,
+        HttpMethod::HEAD,
+        HttpMethod::POST,
+        HttpMethod::PUT,
+        HttpMethod::DELETE,
+        HttpMethod::CONNECT,
+        HttpMethod::OPTIONS,
+        HttpMethod::TRACE,
+        HttpMethod::PATCH,
+    ];
+
+    /// Returns the standard [``HttpMethod``] variant with the highest index,
+    /// see [`HttpMethod::index`].
     pub fn get_last() -> HttpMethod {
         Self::PATCH
     }
+
+    /**
+    Returns the position of a standard method in [`HttpMethod::STANDARD`],
+    or None if the method is an extension method.
+    */
+    pub fn index(&self) -> Option<usize> {
+        let index = match self {
+            HttpMethod::GET => 0,
+            HttpMethod::HEAD => 1,
+            HttpMethod::POST => 2,
+            HttpMethod::PUT => 3,
+            HttpMethod::DELETE => 4,
+            HttpMethod::CONNECT => 5,
+            HttpMethod::OPTIONS => 6,
+            HttpMethod::TRACE => 7,
+            HttpMethod::PATCH => 8,
+            HttpMethod::Extension(_) => return None,
+        };
+        Some(index)
+    }
+
+    /// Returns the name of the method, as it's written in a request line.
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::GET => "GET",
+            HttpMethod::HEAD => "HEAD",
+            HttpMethod::POST => "POST",
+            HttpMethod::PUT => "PUT",
+            HttpMethod::DELETE => "DELETE",
+            HttpMethod::CONNECT => "CONNECT",
+            HttpMethod::OPTIONS => "OPTIONS",
+            HttpMethod::TRACE => "TRACE",
+            HttpMethod::PATCH => "PATCH",
+            HttpMethod::Extension(name) => name,
+        }
+    }
 }
 
 impl PartialEq for HttpMethod {
     fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
+        match (self, other) {
+            (HttpMethod::Extension(a), HttpMethod::Extension(b)) => a == b,
+            (a, b) => mem::discriminant(a) == mem::discriminant(b),
+        }
     }
 }
 
@@ -869,34 +1614,22 @@ impl FromStr for HttpMethod {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "GET" => Ok(HttpMethod::GET),
-            "HEAD" => Ok(HttpMethod::HEAD),
-            "POST" => Ok(HttpMethod::POST),
-            "PUT" => Ok(HttpMethod::PUT),
-            "DELETE" => Ok(HttpMethod::DELETE),
-            "CONNECT" => Ok(HttpMethod::CONNECT),
-            "OPTIONS" => Ok(HttpMethod::OPTIONS),
-            "TRACE" => Ok(HttpMethod::TRACE),
-            "PATCH" => Ok(HttpMethod::PATCH),
-            _ => Err(String::from("invalid http method")),
+        // The names of the methods are case-sensitive, so only the exact name
+        // of a standard method is parsed as the standard variant.
+        if let Some(method) = HttpMethod::STANDARD.iter().find(|m| m.as_str() == s) {
+            return Ok(method.clone());
+        }
+        // method = token
+        if s.is_empty() || !s.chars().all(|c| c.is_valid_token_char()) {
+            return Err(String::from("invalid http method"));
         }
+        Ok(HttpMethod::Extension(s.to_string()))
     }
 }
 
 impl fmt::Display for HttpMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            HttpMethod::CONNECT => write!(f, "CONNECT"),
-            HttpMethod::DELETE => write!(f, "DELETE"),
-            HttpMethod::GET => write!(f, "GET"),
-            HttpMethod::HEAD => write!(f, "HEAD"),
-            HttpMethod::OPTIONS => write!(f, "OPTIONS"),
-            HttpMethod::PATCH => write!(f, "PATCH"),
-            HttpMethod::POST => write!(f, "POST"),
-            HttpMethod::PUT => write!(f, "PUT"),
-            HttpMethod::TRACE => write!(f, "TRACE"),
-        }
+        f.write_str(self.as_str())
     }
 }
 
@@ -0,0 +1,443 @@
+use std::cmp;
+use std::io::{self, BufRead, Read};
+
+use mime::Mime;
+
+use super::errors::HttpError;
+use super::errors::HttpError::InvalidRequest;
+use super::headers::Headers;
+use super::limits::{Limits, DEFAULT_MAX_BODY_SIZE};
+use super::{Body, HttpResult};
+
+/// Defines the default maximum size, in bytes, of the content of a part of a
+/// multipart body.
+pub const DEFAULT_MAX_PART_SIZE: u64 = DEFAULT_MAX_BODY_SIZE;
+
+/// Defines the default maximum size, in bytes, of a multipart body.
+pub const DEFAULT_MAX_MULTIPART_SIZE: u64 = DEFAULT_MAX_BODY_SIZE;
+
+// The size of the reads done from the content of a multipart body.
+const READ_SIZE: usize = 8 * 1024;
+
+// The maximum length of a boundary:
+// https://datatracker.ietf.org/doc/html/rfc2046#section-5.1.1.
+const MAX_BOUNDARY_LENGTH: usize = 70;
+
+/**
+Defines the limits enforced when reading a multipart body with a
+[`Multipart`]. When a limit is exceeded, reading the body fails with a
+[`HttpError::BodyTooLarge`] error. The headers of each part are read using
+the header limits defined in [`Limits::default`].
+
+# Examples
+
+```
+use wruster::http::MultipartLimits;
+
+let limits = MultipartLimits {
+    max_part_size: 1024 * 1024,
+    ..MultipartLimits::default()
+};
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartLimits {
+    /// Maximum size of the content of a part.
+    pub max_part_size: u64,
+    /// Maximum size of the whole multipart body, including the boundaries
+    /// and the headers of the parts.
+    pub max_size: u64,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        MultipartLimits {
+            max_part_size: DEFAULT_MAX_PART_SIZE,
+            max_size: DEFAULT_MAX_MULTIPART_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    // Reading the preamble or the content of a part.
+    Content,
+    // A delimiter has just been read.
+    Delimiter,
+    // The close delimiter has been read.
+    Done,
+}
+
+/**
+Reads the parts of a ``multipart/form-data`` body, as specified in:
+<https://datatracker.ietf.org/doc/html/rfc7578>, without buffering them. The
+parts are returned one at a time by [`Multipart::next_part`], and the content
+of a part is read through the [`io::Read`] implementation of [`Part`]. The
+content of a part not fully read is skipped when the next part is requested.
+
+# Examples
+
+```
+use std::io::{Cursor, Read};
+use wruster::http::{Body, Multipart, MultipartLimits};
+
+let content = "--XyZ\r\n\
+    Content-Disposition: form-data; name=\"field\"\r\n\r\n\
+    value\r\n\
+    --XyZ\r\n\
+    Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+    Content-Type: text/plain\r\n\r\n\
+    file content\r\n\
+    --XyZ--\r\n";
+let mime = "multipart/form-data; boundary=XyZ".parse().unwrap();
+let mut body = Body::new(Some(mime), content.len() as u64, Box::new(Cursor::new(content)));
+let mut multipart = Multipart::from_body(&mut body, MultipartLimits::default()).unwrap();
+
+let mut part = multipart.next_part().unwrap().unwrap();
+assert_eq!(part.name(), Some("field"));
+let mut value = String::new();
+part.read_to_string(&mut value).unwrap();
+assert_eq!(value, "value");
+
+let mut part = multipart.next_part().unwrap().unwrap();
+assert_eq!(part.filename(), Some("a.txt"));
+assert_eq!(part.content_type(), Some(mime::TEXT_PLAIN));
+let mut value = String::new();
+part.read_to_string(&mut value).unwrap();
+assert_eq!(value, "file content");
+
+assert!(multipart.next_part().unwrap().is_none());
+```
+*/
+pub struct Multipart<'a> {
+    source: Source<'a>,
+    delimiter: Vec<u8>,
+    limits: MultipartLimits,
+    state: State,
+}
+
+impl<'a> Multipart<'a> {
+    /**
+    Creates a multipart reader for the content of a [`Body`] with the
+    ``multipart/form-data`` content type. The boundary of the parts is taken
+    from the ``boundary`` parameter of the content type.
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the content type of the body
+    is not ``multipart/form-data`` or doesn't have a valid boundary.
+    */
+    pub fn from_body(body: &'a mut Body, limits: MultipartLimits) -> HttpResult<Multipart<'a>> {
+        let boundary = match body.content_type.as_ref() {
+            Some(mime) if mime.type_() == mime::MULTIPART && mime.subtype() == mime::FORM_DATA => {
+                mime.get_param(mime::BOUNDARY)
+                    .map(|boundary| boundary.as_str().to_string())
+            }
+            _ => None,
+        };
+        let boundary = boundary
+            .ok_or_else(|| InvalidRequest(String::from("body is not multipart/form-data")))?;
+        Multipart::new(body, &boundary, limits)
+    }
+
+    /**
+    Creates a multipart reader for a content using the given boundary.
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the boundary is empty or
+    longer than 70 chars.
+    */
+    pub fn new(
+        content: &'a mut dyn Read,
+        boundary: &str,
+        limits: MultipartLimits,
+    ) -> HttpResult<Multipart<'a>> {
+        if boundary.is_empty() || boundary.len() > MAX_BOUNDARY_LENGTH {
+            let msg = format!("invalid multipart boundary {}", boundary);
+            return Err(InvalidRequest(msg));
+        }
+        // The CRLF preceding a delimiter is part of it, the source starts
+        // with a CRLF so the first delimiter can be found in the same way
+        // than the rest.
+        let delimiter = format!("\r\n--{}", boundary).into_bytes();
+        Ok(Multipart {
+            source: Source::new(content, limits.max_size),
+            delimiter,
+            limits,
+            state: State::Content,
+        })
+    }
+
+    /**
+    Returns the next part of the body, or None if there are no more parts.
+
+    # Errors
+
+    Returns an [`HttpError::BodyTooLarge`] if the size of the body exceeds
+    the limits, an [`HttpError::InvalidRequest`] if the body is not a valid
+    multipart body, or the error returned when reading the content of the
+    body.
+    */
+    pub fn next_part(&mut self) -> HttpResult<Option<Part<'_, 'a>>> {
+        // Skip the preamble or the rest of the content of the current part.
+        let mut skipped = [0; READ_SIZE];
+        while self.state == State::Content {
+            self.read_content(&mut skipped).map_err(to_http_error)?;
+        }
+        if self.state == State::Done {
+            return Ok(None);
+        }
+
+        // delimiter         := "--" boundary
+        // close-delimiter   := delimiter "--"
+        // dash-boundary     := "--" boundary transport-padding CRLF
+        self.source.fill(2).map_err(to_http_error)?;
+        if self.source.available().starts_with(b"--") {
+            self.state = State::Done;
+            return Ok(None);
+        }
+        loop {
+            self.source.fill(1).map_err(to_http_error)?;
+            match self.source.available().first() {
+                Some(b' ') | Some(b'\t') => self.source.consume(1),
+                _ => break,
+            }
+        }
+        self.source.fill(2).map_err(to_http_error)?;
+        if !self.source.available().starts_with(b"\r\n") {
+            return Err(InvalidRequest(String::from("invalid multipart delimiter")));
+        }
+        self.source.consume(2);
+
+        let headers = Headers::read_from_with_limits(&mut self.source, &Limits::default())?;
+        debug!("multipart part headers parsed: {:?}", headers);
+        self.state = State::Content;
+        Ok(Some(Part {
+            headers,
+            multipart: self,
+            size: 0,
+        }))
+    }
+
+    // Reads content until the next delimiter, it returns 0 once the
+    // delimiter is reached.
+    fn read_content(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.state != State::Content || buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let available = self.source.available();
+            let delimiter = self.delimiter.as_slice();
+            let len = match find(available, delimiter) {
+                Some(0) => {
+                    self.source.consume(delimiter.len());
+                    self.state = State::Delimiter;
+                    return Ok(0);
+                }
+                Some(pos) => pos,
+                // The bytes that could be the start of a delimiter are kept
+                // until more content is read.
+                None => available.len().saturating_sub(delimiter.len() - 1),
+            };
+            if len > 0 {
+                let len = cmp::min(len, buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                self.source.consume(len);
+                return Ok(len);
+            }
+            if !self.source.read_more()? {
+                let msg = "multipart body without close delimiter";
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, msg));
+            }
+        }
+    }
+}
+
+/**
+Represents a part of a multipart body returned by [`Multipart::next_part`].
+The content of the part is read using its [`io::Read`] implementation.
+*/
+pub struct Part<'m, 'a> {
+    /// The headers of the part.
+    pub headers: Headers,
+    multipart: &'m mut Multipart<'a>,
+    size: u64,
+}
+
+impl<'m, 'a> Part<'m, 'a> {
+    /// Returns the name of the form field of the part, that is: the ``name``
+    /// parameter of its ``Content-Disposition`` header.
+    pub fn name(&self) -> Option<&str> {
+        self.disposition_param("name")
+    }
+
+    /// Returns the file name of the part, if it contains a file, that is:
+    /// the ``filename`` parameter of its ``Content-Disposition`` header.
+    pub fn filename(&self) -> Option<&str> {
+        self.disposition_param("filename")
+    }
+
+    /// Returns the content type of the part, if present.
+    pub fn content_type(&self) -> Option<Mime> {
+        self.headers.content_type()
+    }
+
+    fn disposition_param(&self, name: &str) -> Option<&str> {
+        let disposition = self.headers.get_first("Content-Disposition")?;
+        disposition_params(disposition)
+            .into_iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
+impl<'m, 'a> Read for Part<'m, 'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.multipart.read_content(buf)?;
+        self.size += read as u64;
+        if self.size > self.multipart.limits.max_part_size {
+            let err = HttpError::BodyTooLarge;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+        Ok(read)
+    }
+}
+
+// Buffers the content of a multipart body, enforcing its maximum size.
+struct Source<'a> {
+    from: &'a mut dyn Read,
+    buf: Vec<u8>,
+    pos: usize,
+    read: u64,
+    max_size: u64,
+}
+
+impl<'a> Source<'a> {
+    fn new(from: &'a mut dyn Read, max_size: u64) -> Self {
+        Source {
+            from,
+            buf: b"\r\n".to_vec(),
+            pos: 0,
+            read: 0,
+            max_size,
+        }
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    // Reads more content into the buffer, it returns false if there is no
+    // more content.
+    fn read_more(&mut self) -> io::Result<bool> {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+        let read = match self.from.read(&mut self.buf[len..]) {
+            Ok(read) => read,
+            Err(err) => {
+                self.buf.truncate(len);
+                return Err(err);
+            }
+        };
+        self.buf.truncate(len + read);
+        self.read += read as u64;
+        if self.read > self.max_size {
+            let err = HttpError::BodyTooLarge;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+        Ok(read > 0)
+    }
+
+    // Ensures that at least ``len`` bytes are available.
+    fn fill(&mut self, len: usize) -> io::Result<()> {
+        while self.available().len() < len {
+            if !self.read_more()? {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Read for Source<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = cmp::min(available.len(), buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<'a> BufRead for Source<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.available().is_empty() {
+            self.read_more()?;
+        }
+        Ok(self.available())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.buf.len());
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// Returns the parameters of a Content-Disposition header:
+// https://datatracker.ietf.org/doc/html/rfc6266#section-4.1, the values in
+// quoted strings are unquoted.
+fn disposition_params(disposition: &str) -> Vec<(&str, &str)> {
+    let mut params = Vec::new();
+    let mut rest = match disposition.split_once(';') {
+        None => return params,
+        Some((_, rest)) => rest,
+    };
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.trim();
+        let value = value.trim_start();
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                // Browsers don't escape the quotes in the file names, they
+                // percent-encode them, so the value ends at the next quote.
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let next = quoted.get(end + 1..).unwrap_or("");
+                (&quoted[..end], next)
+            }
+            None => match value.find(';') {
+                None => (value.trim_end(), ""),
+                Some(end) => (value[..end].trim_end(), &value[end..]),
+            },
+        };
+        params.push((name, value));
+        rest = match next.split_once(';') {
+            None => break,
+            Some((_, rest)) => rest,
+        };
+    }
+    params
+}
+
+fn to_http_error(err: io::Error) -> HttpError {
+    if err.get_ref().map(|inner| inner.is::<HttpError>()) != Some(true) {
+        return match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                InvalidRequest(String::from("unexpected end of multipart body"))
+            }
+            _ => HttpError::from(err),
+        };
+    }
+    match err.into_inner().map(|inner| inner.downcast::<HttpError>()) {
+        Some(Ok(err)) => *err,
+        _ => HttpError::Unknown(String::from("error reading multipart body")),
+    }
+}
@@ -0,0 +1,298 @@
+/*!
+Contains the helpers to choose the representation of a response based on the
+``Accept``, ``Accept-Language`` and ``Accept-Encoding`` headers of a request,
+as specified in: <https://datatracker.ietf.org/doc/html/rfc7231#section-5.3>.
+*/
+use std::cmp::Reverse;
+
+use mime::Mime;
+
+use super::headers::Headers;
+use super::StatusCode;
+
+// The quality of the elements without a q parameter, in thousandths.
+const MAX_QUALITY: u16 = 1000;
+
+/**
+Represents an element of an ``Accept*`` header together with its weight, as
+defined in: <https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.1>.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualityItem {
+    /// The value of the element, like ``text/html``, ``en-US`` or ``gzip``.
+    pub value: String,
+    /// The parameters of the element that precede the ``q`` parameter, only
+    /// present in the media ranges of the ``Accept`` header.
+    pub params: Vec<(String, String)>,
+    /// The weight of the element, in thousandths, from 0 to 1000.
+    pub quality: u16,
+}
+
+/**
+Parses the elements of an ``Accept*`` header, it returns them sorted by their
+quality, in descending order, keeping the order of the header for the ones
+with the same quality. The elements with an invalid quality are ignored.
+
+# Examples
+
+```
+use wruster::http::headers::{Header, Headers};
+use wruster::http::negotiation::parse_quality_items;
+
+let mut headers = Headers::new();
+headers.add(Header::new("Accept-Language", "en;q=0.5, ca, es;q=0.8"));
+let items = parse_quality_items(&headers, "Accept-Language");
+let languages: Vec<&str> = items.iter().map(|item| item.value.as_str()).collect();
+assert_eq!(languages, vec!["ca", "es", "en"]);
+assert_eq!(items[1].quality, 800);
+```
+*/
+pub fn parse_quality_items(headers: &Headers, name: &str) -> Vec<QualityItem> {
+    let mut items: Vec<QualityItem> = headers
+        .list_values(name)
+        .filter_map(parse_quality_item)
+        .collect();
+    // The sort is stable, so the order of the header is kept.
+    items.sort_by_key(|item| Reverse(item.quality));
+    items
+}
+
+/**
+Returns the media type, from the ones supported by the server, that best
+matches the ``Accept`` header of a request. If the request doesn't have the
+header, the first supported media type is returned. When several media types
+have the same quality, the first one in the supported list is chosen.
+
+# Examples
+
+```
+use wruster::http::headers::{Header, Headers};
+use wruster::http::negotiation::negotiate;
+
+let mut headers = Headers::new();
+headers.add(Header::new("Accept", "text/html;q=0.9, text/csv"));
+let supported = [mime::APPLICATION_JSON, mime::TEXT_CSV, mime::TEXT_HTML];
+assert_eq!(negotiate(&headers, &supported).unwrap(), mime::TEXT_CSV);
+```
+
+# Errors
+
+Returns the [`StatusCode::NotAcceptable`] status code if none of the
+supported media types is acceptable, so it can be used to build the response
+of the request.
+*/
+pub fn negotiate(headers: &Headers, supported: &[Mime]) -> Result<Mime, StatusCode> {
+    let accepted = match headers.get("Accept") {
+        None => return supported.first().cloned().ok_or_else(not_acceptable),
+        Some(_) => parse_quality_items(headers, "Accept"),
+    };
+    let best = best_match(supported, |mime| {
+        accepted
+            .iter()
+            .filter_map(|item| media_range_precedence(item, mime).map(|p| (p, item.quality)))
+            .max_by_key(|(precedence, _)| *precedence)
+            .map(|(_, quality)| quality)
+    });
+    best.cloned().ok_or_else(not_acceptable)
+}
+
+/**
+Returns the language, from the ones supported by the server, that best
+matches the ``Accept-Language`` header of a request, using the basic
+filtering defined in: <https://datatracker.ietf.org/doc/html/rfc4647#section-3.3.1>.
+If the request doesn't have the header, the first supported language is
+returned.
+
+# Examples
+
+```
+use wruster::http::headers::{Header, Headers};
+use wruster::http::negotiation::negotiate_language;
+
+let mut headers = Headers::new();
+headers.add(Header::new("Accept-Language", "ca, en;q=0.8"));
+let supported = ["en-US", "es-ES"];
+assert_eq!(negotiate_language(&headers, &supported).unwrap(), "en-US");
+```
+
+# Errors
+
+Returns the [`StatusCode::NotAcceptable`] status code if none of the
+supported languages is acceptable.
+*/
+pub fn negotiate_language(headers: &Headers, supported: &[&str]) -> Result<String, StatusCode> {
+    let accepted = match headers.get("Accept-Language") {
+        None => return first(supported),
+        Some(_) => parse_quality_items(headers, "Accept-Language"),
+    };
+    let best = best_match(supported, |language| {
+        accepted
+            .iter()
+            .filter(|item| language_range_matches(&item.value, language))
+            // The longest range is the most specific one.
+            .max_by_key(|item| item.value.len())
+            .map(|item| item.quality)
+    });
+    best.map(|language| language.to_string())
+        .ok_or_else(not_acceptable)
+}
+
+/**
+Returns the content coding, from the ones supported by the server, that best
+matches the ``Accept-Encoding`` header of a request. The ``identity`` coding
+is acceptable unless the header excludes it explicitly, and it's the one
+returned if the request doesn't have the header and it's supported.
+
+# Examples
+
+```
+use wruster::http::headers::{Header, Headers};
+use wruster::http::negotiation::negotiate_encoding;
+
+let mut headers = Headers::new();
+headers.add(Header::new("Accept-Encoding", "gzip;q=0.5, br"));
+let supported = ["identity", "gzip", "br"];
+assert_eq!(negotiate_encoding(&headers, &supported).unwrap(), "br");
+```
+
+# Errors
+
+Returns the [`StatusCode::NotAcceptable`] status code if none of the
+supported codings is acceptable.
+*/
+pub fn negotiate_encoding(headers: &Headers, supported: &[&str]) -> Result<String, StatusCode> {
+    let accepted = match headers.get("Accept-Encoding") {
+        None if supported.contains(&"identity") => return Ok(String::from("identity")),
+        None => return first(supported),
+        Some(_) => parse_quality_items(headers, "Accept-Encoding"),
+    };
+    let find = |coding: &str| {
+        accepted
+            .iter()
+            .find(|item| item.value.eq_ignore_ascii_case(coding))
+            .map(|item| item.quality)
+    };
+    let any = find("*");
+    let best = best_match(supported, |coding| {
+        // https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.4
+        match (find(coding), any) {
+            (Some(quality), _) => Some(quality),
+            (None, Some(quality)) => Some(quality),
+            // The identity coding is always acceptable unless excluded.
+            (None, None) if coding.eq_ignore_ascii_case("identity") => Some(1),
+            (None, None) => None,
+        }
+    });
+    best.map(|coding| coding.to_string())
+        .ok_or_else(not_acceptable)
+}
+
+// Returns the first supported value with the highest quality, the values
+// without quality or with a quality of 0 are not acceptable.
+fn best_match<T, F>(supported: &[T], quality: F) -> Option<&T>
+where
+    F: Fn(&T) -> Option<u16>,
+{
+    let mut best: Option<(&T, u16)> = None;
+    for value in supported {
+        let quality = match quality(value) {
+            Some(quality) if quality > 0 => quality,
+            _ => continue,
+        };
+        match best {
+            Some((_, best_quality)) if best_quality >= quality => continue,
+            _ => best = Some((value, quality)),
+        }
+    }
+    best.map(|(value, _)| value)
+}
+
+// Returns how specific a media range matching a media type is, or None if it
+// doesn't match it.
+fn media_range_precedence(range: &QualityItem, mime: &Mime) -> Option<u8> {
+    let (type_, subtype) = range.value.split_once('/')?;
+    if type_ == "*" && subtype == "*" {
+        return Some(0);
+    }
+    if !type_.eq_ignore_ascii_case(mime.type_().as_str()) {
+        return None;
+    }
+    if subtype == "*" {
+        return Some(1);
+    }
+    if !subtype.eq_ignore_ascii_case(mime.subtype().as_str()) {
+        return None;
+    }
+    if range.params.is_empty() {
+        return Some(2);
+    }
+    let params_match = range.params.iter().all(|(name, value)| {
+        mime.get_param(name.as_str())
+            .map(|param| param.as_str().eq_ignore_ascii_case(value))
+            .unwrap_or(false)
+    });
+    match params_match {
+        true => Some(3),
+        false => None,
+    }
+}
+
+fn language_range_matches(range: &str, language: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+    let prefix_matches = language
+        .get(..range.len())
+        .map_or(false, |prefix| prefix.eq_ignore_ascii_case(range));
+    if !prefix_matches {
+        return false;
+    }
+    language.len() == range.len() || language.as_bytes()[range.len()] == b'-'
+}
+
+fn parse_quality_item(element: &str) -> Option<QualityItem> {
+    let mut parts = element.split(';').map(|part| part.trim());
+    let value = parts.next().filter(|value| !value.is_empty())?;
+    let mut params = Vec::new();
+    let mut quality = MAX_QUALITY;
+    for param in parts {
+        let (name, param_value) = param.split_once('=')?;
+        let (name, param_value) = (name.trim(), param_value.trim().trim_matches('"'));
+        if name.eq_ignore_ascii_case("q") {
+            quality = parse_quality(param_value)?;
+            // The parameters after the weight are extensions.
+            break;
+        }
+        params.push((name.to_string(), param_value.to_string()));
+    }
+    Some(QualityItem {
+        value: value.to_string(),
+        params,
+        quality,
+    })
+}
+
+// qvalue = ( "0" [ "." 0*3DIGIT ] ) / ( "1" [ "." 0*3("0") ] )
+fn parse_quality(value: &str) -> Option<u16> {
+    let (integer, decimals) = value.split_once('.').unwrap_or((value, ""));
+    if decimals.len() > 3 || !decimals.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let decimals = format!("{:0<3}", decimals).parse::<u16>().ok()?;
+    match integer {
+        "0" => Some(decimals),
+        "1" if decimals == 0 => Some(MAX_QUALITY),
+        _ => None,
+    }
+}
+
+fn first(supported: &[&str]) -> Result<String, StatusCode> {
+    supported
+        .first()
+        .map(|value| value.to_string())
+        .ok_or_else(not_acceptable)
+}
+
+fn not_acceptable() -> StatusCode {
+    StatusCode::NotAcceptable
+}
@@ -0,0 +1,344 @@
+/*!
+Contains the helpers to answer the requests with a ``Range`` header, as
+specified in: <https://datatracker.ietf.org/doc/html/rfc7233>.
+*/
+use std::cmp;
+use std::collections::VecDeque;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mime::Mime;
+
+use super::conditional::{strong_compare, unix_secs};
+use super::headers::{Header, Headers};
+use super::{parse_http_date, Body, Response, StatusCode, Version};
+
+/// The maximum number of ranges served for a request, the requests with more
+/// ranges are answered with the full content.
+pub const MAX_RANGES: usize = 32;
+
+/**
+Represents a satisfiable range of bytes of a content, both of its positions
+are inclusive.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    /// The position of the first byte of the range.
+    pub start: u64,
+    /// The position of the last byte of the range.
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Returns the number of bytes in the range.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn content_range(&self, length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, length)
+    }
+}
+
+/**
+Parses the ``Range`` header of a request for a content with the given
+length. It returns ``None`` if the request doesn't have the header or if it
+must be ignored, because its unit is not ``bytes``, it's invalid or it has
+more than [`MAX_RANGES`] ranges. Otherwise, it returns the satisfiable
+ranges, sorted and with the overlapping ones coalesced.
+
+# Examples
+
+```
+use wruster::http::headers::{Header, Headers};
+use wruster::http::range::{parse_range, ByteRange};
+
+let mut headers = Headers::new();
+headers.add(Header::new("Range", "bytes=0-9, -5"));
+let ranges = parse_range(&headers, 100).unwrap().unwrap();
+assert_eq!(ranges, vec![ByteRange { start: 0, end: 9 }, ByteRange { start: 95, end: 99 }]);
+```
+
+# Errors
+
+Returns the [`StatusCode::RequestedRangeNotSatisfiable`] status code if none
+of the ranges is satisfiable, see [`range_not_satisfiable`].
+*/
+pub fn parse_range(headers: &Headers, length: u64) -> Result<Option<Vec<ByteRange>>, StatusCode> {
+    let value = match headers.get_first("Range") {
+        None => return Ok(None),
+        Some(value) => value.trim(),
+    };
+    let specs = match value.split_once('=') {
+        Some((unit, specs)) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+        _ => return Ok(None),
+    };
+    let specs: Vec<&str> = specs
+        .split(',')
+        .map(|spec| spec.trim())
+        .filter(|spec| !spec.is_empty())
+        .collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return Ok(None);
+    }
+    let mut ranges = Vec::new();
+    for spec in specs {
+        match parse_range_spec(spec, length) {
+            Err(()) => return Ok(None),
+            Ok(None) => continue,
+            Ok(Some(range)) => ranges.push(range),
+        }
+    }
+    if ranges.is_empty() {
+        return Err(StatusCode::RequestedRangeNotSatisfiable);
+    }
+    Ok(Some(coalesce(ranges)))
+}
+
+/**
+Returns true if the request doesn't have an ``If-Range`` header or if its
+validator matches the current representation of the content, as specified in:
+<https://datatracker.ietf.org/doc/html/rfc7233#section-3.2>. An entity-tag
+only matches using the strong comparison, and a date only matches if it's
+exactly the ``Last-Modified`` date of the content, with a resolution of one
+second.
+*/
+pub fn if_range_matches(
+    headers: &Headers,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    let validator = match headers.get_first("If-Range") {
+        None => return true,
+        Some(validator) => validator.trim(),
+    };
+    if validator.starts_with('"') || validator.starts_with("W/") {
+        return etag
+            .map(|etag| strong_compare(validator, etag))
+            .unwrap_or(false);
+    }
+    match (parse_http_date(validator), last_modified) {
+        (Some(date), Some(modified)) => unix_secs(date) == unix_secs(modified),
+        _ => false,
+    }
+}
+
+/**
+Creates a ``206 Partial Content`` response with the given ranges of a
+content of the given length and mime type. A single range is sent as the body
+of the response, together with its ``Content-Range`` header, and several
+ranges are sent in a ``multipart/byteranges`` body.
+
+# Examples
+
+```
+use std::io::{Cursor, Read};
+use wruster::http::range::{partial_content, ByteRange};
+use wruster::http::StatusCode;
+
+let content = Cursor::new("0123456789");
+let ranges = [ByteRange { start: 2, end: 4 }];
+let mut response = partial_content(content, 10, mime::TEXT_PLAIN, &ranges);
+assert_eq!(response.status, StatusCode::PartialContent);
+assert_eq!(response.headers.get_first("Content-Range"), Some("bytes 2-4/10"));
+let mut body = String::new();
+response.body.unwrap().read_to_string(&mut body).unwrap();
+assert_eq!(body, "234");
+```
+*/
+pub fn partial_content<T: Read + Seek + 'static>(
+    content: T,
+    length: u64,
+    mime: Mime,
+    ranges: &[ByteRange],
+) -> Response {
+    let mut headers = Headers::new();
+    let mut segments = VecDeque::new();
+    let (body_mime, body_length) = match ranges {
+        [range] => {
+            headers.add(Header::new("Content-Range", &range.content_range(length)));
+            segments.push_back(Segment::from(range));
+            (mime, range.length())
+        }
+        _ => {
+            let boundary = new_boundary();
+            let mut body_length = 0;
+            for (i, range) in ranges.iter().enumerate() {
+                let delimiter = if i == 0 { "" } else { "\r\n" };
+                let head = format!(
+                    "{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    delimiter,
+                    boundary,
+                    mime,
+                    range.content_range(length)
+                );
+                body_length += head.len() as u64 + range.length();
+                segments.push_back(Segment::Text(Cursor::new(head.into_bytes())));
+                segments.push_back(Segment::from(range));
+            }
+            let tail = format!("\r\n--{}--\r\n", boundary);
+            body_length += tail.len() as u64;
+            segments.push_back(Segment::Text(Cursor::new(tail.into_bytes())));
+            let body_mime = format!("multipart/byteranges; boundary={}", boundary)
+                .parse::<Mime>()
+                .expect("valid multipart/byteranges media type");
+            (body_mime, body_length)
+        }
+    };
+    headers.set_content_length(body_length);
+    headers.set_content_type(&body_mime);
+    let content = Box::new(RangesReader { content, segments });
+    let body = Body::new(Some(body_mime), body_length, content);
+    Response {
+        version: Version::HTTP1_1,
+        status: StatusCode::PartialContent,
+        headers,
+        body: Some(body),
+    }
+}
+
+/// Creates a ``416 Range Not Satisfiable`` response for a content of the given
+/// length, with the ``Content-Range`` header the spec requires.
+///
+/// # Examples
+///
+/// ```
+/// use wruster::http::range::range_not_satisfiable;
+///
+/// let response = range_not_satisfiable(10);
+/// assert_eq!(response.headers.get_first("Content-Range"), Some("bytes */10"));
+/// ```
+pub fn range_not_satisfiable(length: u64) -> Response {
+    let mut response = Response::from_status(StatusCode::RequestedRangeNotSatisfiable);
+    let content_range = format!("bytes */{}", length);
+    response
+        .headers
+        .add(Header::new("Content-Range", &content_range));
+    response
+}
+
+// Parses a byte-range-spec or a suffix-byte-range-spec. It returns an error
+// if the spec is invalid and None if it's not satisfiable.
+fn parse_range_spec(spec: &str, length: u64) -> Result<Option<ByteRange>, ()> {
+    let (first, last) = spec.split_once('-').ok_or(())?;
+    let (first, last) = (first.trim(), last.trim());
+    let parse = |pos: &str| {
+        if pos.is_empty() || !pos.chars().all(|c| c.is_ascii_digit()) {
+            return Err(());
+        }
+        pos.parse::<u64>().map_err(|_| ())
+    };
+    if first.is_empty() {
+        let suffix = parse(last)?;
+        if suffix == 0 || length == 0 {
+            return Ok(None);
+        }
+        let start = length.saturating_sub(suffix);
+        return Ok(Some(ByteRange {
+            start,
+            end: length - 1,
+        }));
+    }
+    let start = parse(first)?;
+    let end = match last {
+        "" => u64::MAX,
+        last => parse(last)?,
+    };
+    if end < start {
+        return Err(());
+    }
+    if start >= length {
+        return Ok(None);
+    }
+    Ok(Some(ByteRange {
+        start,
+        end: cmp::min(end, length - 1),
+    }))
+}
+
+fn coalesce(mut ranges: Vec<ByteRange>) -> Vec<ByteRange> {
+    ranges.sort_by_key(|range| range.start);
+    let mut coalesced: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = cmp::max(last.end, range.end)
+            }
+            _ => coalesced.push(range),
+        }
+    }
+    coalesced
+}
+
+fn new_boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!("wruster{:x}", nanos)
+}
+
+enum Segment {
+    Text(Cursor<Vec<u8>>),
+    Range {
+        start: u64,
+        remaining: u64,
+        sought: bool,
+    },
+}
+
+impl From<&ByteRange> for Segment {
+    fn from(range: &ByteRange) -> Self {
+        Segment::Range {
+            start: range.start,
+            remaining: range.length(),
+            sought: false,
+        }
+    }
+}
+
+// Reads the ranges of a content, together with the text that precedes each of
+// them, seeking the content only when a range starts to be read.
+struct RangesReader<T> {
+    content: T,
+    segments: VecDeque<Segment>,
+}
+
+impl<T: Read + Seek> Read for RangesReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while let Some(segment) = self.segments.front_mut() {
+            match segment {
+                Segment::Text(text) => {
+                    let read = text.read(buf)?;
+                    if read > 0 {
+                        return Ok(read);
+                    }
+                }
+                Segment::Range {
+                    start,
+                    remaining,
+                    sought,
+                } => {
+                    if *remaining > 0 {
+                        if !*sought {
+                            self.content.seek(SeekFrom::Start(*start))?;
+                            *sought = true;
+                        }
+                        let max = cmp::min(buf.len() as u64, *remaining) as usize;
+                        let read = self.content.read(&mut buf[..max])?;
+                        if read == 0 {
+                            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                        }
+                        *remaining -= read as u64;
+                        return Ok(read);
+                    }
+                }
+            }
+            self.segments.pop_front();
+        }
+        Ok(0)
+    }
+}
@@ -1,10 +1,11 @@
 use std::convert::From;
 use std::fmt;
 
-#[allow(missing_docs)]
-
 /// Contains a variant for each defined status code according to
-/// the spec in: https://datatracker.ietf.org/doc/html/rfc7231#section-6.1.
+/// the spec in: https://datatracker.ietf.org/doc/html/rfc7231#section-6.1,
+/// plus the 431 status code defined in:
+/// https://datatracker.ietf.org/doc/html/rfc6585#section-5.
+#[allow(missing_docs)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum StatusCode {
     // https://datatracker.ietf.org/doc/html/rfc7231#section-6.1
@@ -42,6 +43,7 @@ pub enum StatusCode {
     UnsupportedMediaType,
     RequestedRangeNotSatisfiable,
     ExpectationFailed,
+    RequestHeaderFieldsTooLarge,
     InternalServerError,
     NotImplemented,
     BadGateway,
@@ -100,6 +102,7 @@ impl StatusCode {
             StatusCode::UnsupportedMediaType => "Unsupported Media Type",
             StatusCode::RequestedRangeNotSatisfiable => "Requested range not satisfiable",
             StatusCode::ExpectationFailed => "Expectation Failed",
+            StatusCode::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
             StatusCode::InternalServerError => "Internal Server Error",
             StatusCode::NotImplemented => "Not Implemented",
             StatusCode::BadGateway => "Bad Gateway",
@@ -148,6 +151,7 @@ impl From<usize> for StatusCode {
             415 => Self::UnsupportedMediaType,
             416 => Self::RequestedRangeNotSatisfiable,
             417 => Self::ExpectationFailed,
+            431 => Self::RequestHeaderFieldsTooLarge,
             500 => Self::InternalServerError,
             501 => Self::NotImplemented,
             502 => Self::BadGateway,
@@ -196,6 +200,7 @@ impl From<&StatusCode> for usize {
             StatusCode::UnsupportedMediaType => 415,
             StatusCode::RequestedRangeNotSatisfiable => 416,
             StatusCode::ExpectationFailed => 417,
+            StatusCode::RequestHeaderFieldsTooLarge => 431,
             StatusCode::InternalServerError => 500,
             StatusCode::NotImplemented => 501,
             StatusCode::BadGateway => 502,
@@ -258,6 +263,7 @@ impl Clone for StatusCode {
             Self::UnsupportedMediaType => Self::UnsupportedMediaType,
             Self::RequestedRangeNotSatisfiable => Self::RequestedRangeNotSatisfiable,
             Self::ExpectationFailed => Self::ExpectationFailed,
+            Self::RequestHeaderFieldsTooLarge => Self::RequestHeaderFieldsTooLarge,
             Self::InternalServerError => Self::InternalServerError,
             Self::NotImplemented => Self::NotImplemented,
             Self::BadGateway => Self::BadGateway,
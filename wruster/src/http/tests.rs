@@ -125,7 +125,7 @@ test";
     let mut payload = String::new();
     body.content.read_to_string(&mut payload).unwrap();
 
-    assert_eq!(req.uri, "/file");
+    assert_eq!(req.uri.path(), "/file");
     assert_eq!(req.method, HttpMethod::POST);
     assert_eq!(&payload, "test");
 }
@@ -176,6 +176,7 @@ fn http_response_write() {
         value: String::from("8"),
     });
     let mut response = Response {
+        version: Version::HTTP1_1,
         status: StatusCode::OK,
         headers: headers,
         body: Some(body),
@@ -193,6 +194,7 @@ fn http_response_write() {
 fn http_response_write_empty_body() {
     let headers = Headers::new();
     let mut response = Response {
+        version: Version::HTTP1_1,
         status: StatusCode::OK,
         headers: headers,
         body: None,
@@ -210,6 +212,7 @@ fn http_response_write_empty_body() {
 fn http_response_no_headers_no_body() {
     let headers = Headers::new();
     let mut response = Response {
+        version: Version::HTTP1_1,
         status: StatusCode::OK,
         headers: headers,
         body: None,
@@ -278,3 +281,1053 @@ fn http_body_read_updates_read_bytes() {
     assert_eq!(size, 4);
     assert_eq!(body.bytes_read, 4)
 }
+
+fn chunked_headers() -> Headers {
+    let mut headers = Headers::new();
+    headers.add(Header {
+        name: "Transfer-Encoding".to_string(),
+        value: "chunked".to_string(),
+    });
+    headers
+}
+
+#[test]
+fn http_body_read_from_chunked() {
+    let from = Cursor::new("4\r\ntest\r\n6;ext=value;other\r\n chunk\r\n0\r\n\r\n");
+    let mut body = Body::read_from(from, &chunked_headers())
+        .expect("reading body")
+        .unwrap();
+    assert!(body.is_chunked());
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "test chunk");
+    assert_eq!(body.trailers().unwrap().iter().count(), 0);
+}
+
+#[test]
+fn http_body_read_from_chunked_trailers() {
+    let from = Cursor::new("4\r\ntest\r\n0\r\nChecksum: abc\r\nExpires: never\r\n\r\n");
+    let mut body = Body::read_from(from, &chunked_headers())
+        .expect("reading body")
+        .unwrap();
+    assert!(body.trailers().is_none());
+    body.ensure_read().unwrap();
+    let trailers = body.trailers().unwrap();
+    assert_eq!(trailers.get("Checksum"), Some(&vec!["abc".to_string()]));
+    assert_eq!(trailers.get("Expires"), Some(&vec!["never".to_string()]));
+}
+
+#[test]
+fn http_body_read_from_chunked_ignores_content_length() {
+    let from = Cursor::new("4\r\ntest\r\n0\r\n\r\n");
+    let mut headers = chunked_headers();
+    headers.add(Header {
+        name: "Content-Length".to_string(),
+        value: "2".to_string(),
+    });
+    let mut body = Body::read_from(from, &headers)
+        .expect("reading body")
+        .unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "test");
+}
+
+#[test]
+fn http_body_read_from_chunked_quoted_extensions() {
+    let from =
+        Cursor::new("4;name=\"a;b\"\r\ntest\r\n1 ; q = \"x\\\"y;\\\\\" ;e\r\n!\r\n0\r\n\r\n");
+    let mut body = Body::read_from(from, &chunked_headers())
+        .expect("reading body")
+        .unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "test!");
+}
+
+#[test]
+fn http_body_read_from_chunked_invalid() {
+    let invalid = [
+        "x\r\ntest\r\n0\r\n\r\n",
+        "4\r\ntestxx0\r\n\r\n",
+        "4\ntest\r\n0\r\n\r\n",
+        "4;\r\ntest\r\n0\r\n\r\n",
+        "4;a=\"b\r\ntest\r\n0\r\n\r\n",
+        "4;a=b c\r\ntest\r\n0\r\n\r\n",
+        "4\r\nte",
+        "11111111111111111\r\ntest\r\n0\r\n\r\n",
+    ];
+    for content in invalid {
+        let from = Cursor::new(content);
+        let mut body = Body::read_from(from, &chunked_headers())
+            .expect("reading body")
+            .unwrap();
+        let mut buf = Vec::new();
+        assert!(body.read_to_end(&mut buf).is_err(), "{:?}", content);
+    }
+}
+
+#[test]
+fn http_body_read_from_unsupported_transfer_encoding() {
+    let encodings = ["gzip", "chunked, gzip", "chunked, chunked", ""];
+    for encoding in encodings {
+        let from = Cursor::new("4\r\ntest\r\n0\r\n\r\n");
+        let mut headers = Headers::new();
+        headers.add(Header {
+            name: "Transfer-Encoding".to_string(),
+            value: encoding.to_string(),
+        });
+        assert!(Body::read_from(from, &headers).is_err(), "{:?}", encoding);
+    }
+}
+
+#[test]
+fn http_body_read_from_chunked_limits_size_line() {
+    let limits = Limits {
+        max_header_line_length: 16,
+        ..Limits::default()
+    };
+    let from = format!("1;ext={}\r\na\r\n0\r\n\r\n", "a".repeat(16));
+    let mut body = Body::read_from_with_limits(Cursor::new(from), &chunked_headers(), &limits)
+        .unwrap()
+        .unwrap();
+    let mut content = String::new();
+    let err = body.read_to_string(&mut content).unwrap_err();
+    assert_eq!(HttpError::from(err), HttpError::BodyTooLarge);
+    // The body keeps failing after the limit is exceeded.
+    assert_eq!(body.ensure_read(), Err(HttpError::BodyTooLarge));
+}
+
+#[test]
+fn http_body_chunked_ensure_read_stops_at_message_end() {
+    let content = "4\r\ntest\r\n0\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+    let mut reader = BufReader::new(Cursor::new(content));
+    let mut chunked = chunked::ChunkedReader::new(&mut reader, Limits::unlimited());
+    io::copy(&mut chunked, &mut io::sink()).unwrap();
+    drop(chunked);
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "GET / HTTP/1.1\r\n\r\n");
+}
+
+#[test]
+fn http_request_from_str_chunked() {
+    let str_req = "POST /file HTTP/1.1\r\n\
+Transfer-Encoding: chunked\r\n\
+\r\n\
+2\r\n\
+te\r\n\
+2\r\n\
+st\r\n\
+0\r\n\
+\r\n";
+    let req = Request::read_from_str(str_req).unwrap();
+    let mut body = req.body.unwrap();
+    let mut payload = String::new();
+    body.content.read_to_string(&mut payload).unwrap();
+    assert_eq!(&payload, "test");
+}
+
+#[test]
+fn http_response_read_from_chunked() {
+    let str_resp = "HTTP/1.1 200 OK\r\n\
+Transfer-Encoding: chunked\r\n\
+\r\n\
+4\r\n\
+test\r\n\
+0\r\n\
+\r\n";
+    let resp = Response::read_from(Cursor::new(str_resp)).unwrap();
+    let mut body = resp.body.unwrap();
+    let mut payload = String::new();
+    body.read_to_string(&mut payload).unwrap();
+    assert_eq!(&payload, "test");
+}
+
+#[test]
+fn http_body_write_chunked() {
+    let content = "#wruster";
+    let mut body = Body::chunked(Some(mime::TEXT_PLAIN), Box::new(Cursor::new(content)));
+    let mut to: Vec<u8> = Vec::new();
+    body.write(&mut to).unwrap();
+    let got = String::from_utf8(to).unwrap();
+    assert_eq!("8\r\n#wruster\r\n0\r\n\r\n", &got);
+
+    // An empty body only contains the last chunk.
+    let mut body = Body::chunked(None, Box::new(Cursor::new("")));
+    let mut to: Vec<u8> = Vec::new();
+    body.write(&mut to).unwrap();
+    let got = String::from_utf8(to).unwrap();
+    assert_eq!("0\r\n\r\n", &got);
+}
+
+#[test]
+fn http_body_write_chunked_large_content() {
+    let content = vec![b'a'; 100 * 1024];
+    let mut body = Body::chunked(None, Box::new(Cursor::new(content.clone())));
+    let mut to: Vec<u8> = Vec::new();
+    body.write(&mut to).unwrap();
+
+    // Check the content can be decoded back.
+    let mut reader = chunked::ChunkedReader::new(Cursor::new(to), Limits::unlimited());
+    let mut got = Vec::new();
+    reader.read_to_end(&mut got).unwrap();
+    assert_eq!(content, got);
+}
+
+#[test]
+fn http_body_write_chunked_flushes_each_chunk() {
+    struct FlushRecorder {
+        written: Vec<u8>,
+        flushed: Vec<String>,
+    }
+
+    impl Write for FlushRecorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            let written = String::from_utf8(self.written.clone()).unwrap();
+            self.flushed.push(written);
+            Ok(())
+        }
+    }
+
+    let content = Cursor::new("ab").chain(Cursor::new("cd"));
+    let mut body = Body::chunked(None, Box::new(content));
+    let mut to = FlushRecorder {
+        written: Vec::new(),
+        flushed: Vec::new(),
+    };
+    body.write(&mut to).unwrap();
+    assert_eq!(
+        to.flushed,
+        vec![
+            "2\r\nab\r\n",
+            "2\r\nab\r\n2\r\ncd\r\n",
+            "2\r\nab\r\n2\r\ncd\r\n0\r\n\r\n",
+        ]
+    );
+}
+
+#[test]
+fn http_response_write_chunked_removes_content_length() {
+    let mut response = Response::from_chunked_content(Cursor::new("#wruster"), mime::TEXT_PLAIN);
+    response.headers.set_content_length(8);
+    let mut to: Vec<u8> = Vec::new();
+    response.write(&mut to).unwrap();
+    let got = String::from_utf8(to).unwrap();
+    assert!(got.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(!got.contains("Content-Length"));
+}
+
+#[test]
+fn http_response_write_chunked() {
+    let mut response = Response::from_chunked_content(Cursor::new("#wruster"), mime::TEXT_PLAIN);
+    let mut trailers = Headers::new();
+    trailers.add(Header {
+        name: String::from("Checksum"),
+        value: String::from("abc"),
+    });
+    response.body.as_mut().unwrap().set_trailers(trailers);
+    let mut to: Vec<u8> = Vec::new();
+    response.write(&mut to).unwrap();
+    let got = String::from_utf8(to.clone()).unwrap();
+    assert!(got.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(got.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(got.contains("Trailer: Checksum\r\n"));
+    assert!(!got.contains("Content-Length"));
+    assert!(got.ends_with("\r\n\r\n8\r\n#wruster\r\n0\r\nChecksum: abc\r\n\r\n"));
+
+    // Check the response can be read back.
+    let response = Response::read_from(Cursor::new(to)).unwrap();
+    let mut body = response.body.unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "#wruster");
+    let trailers = body.trailers().unwrap();
+    assert_eq!(trailers.get("Checksum"), Some(&vec!["abc".to_string()]));
+}
+
+#[test]
+fn http_request_write_chunked() {
+    let body = Body::chunked(Some(mime::TEXT_PLAIN), Box::new(Cursor::new("body")));
+    let req = Request::from_body(body, HttpMethod::POST, "/");
+    let mut to: Vec<u8> = Vec::new();
+    req.write(&mut to).unwrap();
+
+    let req = Request::read_from(Cursor::new(to)).unwrap();
+    assert_eq!(
+        req.headers.get("Transfer-Encoding"),
+        Some(&vec!["chunked".to_string()])
+    );
+    let mut body = req.body.unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "body");
+}
+
+#[test]
+fn http_uri_parse() {
+    let uri = Uri::parse("/a//b/./c/../d%20e/?x=1&y=a+b&x=%2F&z").unwrap();
+    assert_eq!(uri.path(), "/a/b/d e/");
+    assert_eq!(uri.raw_path(), "/a//b/./c/../d%20e/");
+    assert_eq!(uri.query(), Some("x=1&y=a+b&x=%2F&z"));
+    assert_eq!(
+        uri.query_params().get("x"),
+        Some(&vec!["1".to_string(), "/".to_string()])
+    );
+    assert_eq!(uri.query_param("y"), Some("a b"));
+    assert_eq!(uri.query_param("z"), Some(""));
+    assert_eq!(uri.query_param("w"), None);
+    assert_eq!(uri.to_string(), "/a//b/./c/../d%20e/?x=1&y=a+b&x=%2F&z");
+
+    let uri = Uri::parse("/").unwrap();
+    assert_eq!(uri.path(), "/");
+    assert_eq!(uri.query(), None);
+
+    let uri = Uri::parse("/a/b/..").unwrap();
+    assert_eq!(uri.path(), "/a/");
+
+    let uri = Uri::parse("/a%2Fb").unwrap();
+    assert_eq!(uri.path(), "/a/b");
+
+    let uri = Uri::parse("http://example.com:8080/a?x=1").unwrap();
+    assert_eq!(uri.path(), "/a");
+    assert_eq!(uri.query_param("x"), Some("1"));
+    let uri = Uri::parse("http://example.com").unwrap();
+    assert_eq!(uri.path(), "/");
+
+    let uri = Uri::parse("*").unwrap();
+    assert_eq!(uri.path(), "*");
+}
+
+#[test]
+fn http_uri_parse_rejects_invalid() {
+    let invalid = [
+        "",
+        "a/b",
+        "/a#fragment",
+        "/a/%2e%2e/b",
+        "/a/%2E./b",
+        "/a/.%2e",
+        "/a/%2F..%2F..%2Fetc",
+        "/..",
+        "/a/../../b",
+        "/a%00",
+        "/a%zz",
+        "/a%2",
+        "/a%+1",
+        "/%FF",
+    ];
+    for uri in invalid {
+        assert!(
+            matches!(Uri::parse(uri), Err(HttpError::InvalidRequest(_))),
+            "uri {:?} should be invalid",
+            uri
+        );
+    }
+}
+
+#[test]
+fn http_request_read_uri() {
+    let req = Request::read_from_str("GET /a/%62?c=d HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(req.uri.path(), "/a/b");
+    assert_eq!(req.uri.raw_path(), "/a/%62");
+    assert_eq!(req.uri.query_param("c"), Some("d"));
+
+    let err = Request::read_from_str("GET /a/%2e%2e/b HTTP/1.1\r\n\r\n").unwrap_err();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+}
+
+#[test]
+fn http_request_read_version() {
+    let req = Request::read_from_str("GET / HTTP/1.0\r\n\r\n").unwrap();
+    assert_eq!(req.version, Version::HTTP1_0);
+    assert!(!req.is_connection_persistent());
+
+    let req = Request::read_from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(req.version, Version::HTTP1_1);
+    assert!(req.is_connection_persistent());
+
+    let err = Request::read_from_str("GET / HTTP/1.2\r\n\r\n").unwrap_err();
+    assert_eq!(
+        err,
+        HttpError::VersionNotSupported(String::from("HTTP/1.2"))
+    );
+
+    let err = Request::read_from_str("GET / HTTP/a\r\n\r\n").unwrap_err();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+}
+
+#[test]
+fn http_response_write_version() {
+    let mut response = Response::from_status(StatusCode::OK);
+    response.version = Version::HTTP1_0;
+    let mut to: Vec<u8> = Vec::new();
+    response.write(&mut to).unwrap();
+    let written = String::from_utf8(to).unwrap();
+    assert!(written.starts_with("HTTP/1.0 200 OK\r\n"));
+
+    let content = Cursor::new("chunked");
+    let mut response = Response::from_chunked_content(content, mime::TEXT_PLAIN);
+    response.version = Version::HTTP1_0;
+    assert!(response.is_close_delimited());
+    let mut to: Vec<u8> = Vec::new();
+    response.write(&mut to).unwrap();
+    let written = String::from_utf8(to).unwrap();
+    assert!(!written.contains("Transfer-Encoding"));
+    assert!(written.ends_with("\r\n\r\nchunked"));
+}
+
+#[test]
+fn http_request_read_extension_method() {
+    let req = Request::read_from_str("PROPFIND /a HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(req.method, HttpMethod::Extension(String::from("PROPFIND")));
+    assert_eq!(req.method.index(), None);
+    assert_eq!(req.method.to_string(), "PROPFIND");
+
+    let req = Request::read_from_str("GET /a HTTP/1.1\r\n\r\n").unwrap();
+    assert!(matches!(req.method, HttpMethod::GET));
+    assert_eq!(req.method.index(), Some(0));
+
+    // The names of the methods are case-sensitive.
+    let req = Request::read_from_str("get /a HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(req.method, HttpMethod::Extension(String::from("get")));
+    assert_ne!(req.method, HttpMethod::GET);
+
+    let err = Request::read_from_str("PROP(FIND /a HTTP/1.1\r\n\r\n").unwrap_err();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+}
+
+#[test]
+fn http_method_eq() {
+    assert_eq!(HttpMethod::GET, HttpMethod::GET);
+    assert_ne!(HttpMethod::GET, HttpMethod::HEAD);
+    let propfind = HttpMethod::Extension(String::from("PROPFIND"));
+    assert_eq!(propfind, HttpMethod::Extension(String::from("PROPFIND")));
+    assert_ne!(propfind, HttpMethod::Extension(String::from("MKCOL")));
+    assert_ne!(propfind, HttpMethod::GET);
+    for method in HttpMethod::STANDARD {
+        assert_eq!(HttpMethod::from_str(method.as_str()), Ok(method));
+    }
+}
+
+#[test]
+fn http_request_cookies() {
+    let str_req =
+        "GET / HTTP/1.1\r\nCookie: a=1; b=\"quoted value\";c=;invalid; =x\r\nCookie: a=2\r\n\r\n";
+    let req = Request::read_from_str(str_req).unwrap();
+    let cookies = req.cookies();
+    assert_eq!(cookies.len(), 3);
+    assert_eq!(cookies["a"], "1");
+    assert_eq!(cookies["b"], "quoted value");
+    assert_eq!(cookies["c"], "");
+
+    let req = Request::read_from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    assert!(req.cookies().is_empty());
+}
+
+#[test]
+fn http_response_add_cookie() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let mut response = Response::from_status(StatusCode::OK);
+    let cookie = Cookie::new("id", "a3fWa")
+        .domain("example.com")
+        .path("/docs")
+        .expires(UNIX_EPOCH + Duration::from_secs(1445412480))
+        .same_site(SameSite::None)
+        .secure();
+    response.add_cookie(&cookie).unwrap();
+    response.add_cookie(&Cookie::new("lang", "en")).unwrap();
+    let mut to: Vec<u8> = Vec::new();
+    response.write(&mut to).unwrap();
+    let written = String::from_utf8(to).unwrap();
+    assert!(written.contains(
+        "Set-Cookie: id=a3fWa; Path=/docs; Domain=example.com; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; SameSite=None\r\n"
+    ));
+    assert!(written.contains("Set-Cookie: lang=en\r\n"));
+
+    let invalid = [
+        Cookie::new("", "a"),
+        Cookie::new("a b", "a"),
+        Cookie::new("a", "b;c"),
+        Cookie::new("a", "b c"),
+        Cookie::new("a", "\"b").path("/"),
+        Cookie::new("a", "b").path("/;Secure"),
+        Cookie::new("a", "b").same_site(SameSite::None),
+    ];
+    for cookie in invalid.iter() {
+        let err = response.add_cookie(cookie).unwrap_err();
+        assert!(matches!(err, HttpError::InvalidCookie(_)));
+    }
+    assert!(response.add_cookie(&Cookie::new("a", "\"b\"")).is_ok());
+}
+
+// Returns the content in reads of one byte, so the delimiters of a multipart
+// body are split across reads.
+struct ByteReader<R: Read>(R);
+
+impl<R: Read> Read for ByteReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = std::cmp::min(1, buf.len());
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn http_multipart_read_parts() {
+    let content = "preamble\r\n--b0undary \r\n\
+        Content-Disposition: form-data; name=\"skipped\"\r\n\r\n\
+        not read\r\n--b0undary\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a b.txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        line 1\r\n--b0und\r\nline 2\r\n--b0undary--\r\nepilogue";
+    let mut from = ByteReader(Cursor::new(content));
+    let limits = MultipartLimits::default();
+    let mut multipart = Multipart::new(&mut from, "b0undary", limits).unwrap();
+
+    let part = multipart.next_part().unwrap().unwrap();
+    assert_eq!(part.name(), Some("skipped"));
+    assert_eq!(part.filename(), None);
+
+    let mut part = multipart.next_part().unwrap().unwrap();
+    assert_eq!(part.name(), Some("file"));
+    assert_eq!(part.filename(), Some("a b.txt"));
+    assert_eq!(part.content_type(), Some(mime::TEXT_PLAIN));
+    let mut file = String::new();
+    part.read_to_string(&mut file).unwrap();
+    assert_eq!(file, "line 1\r\n--b0und\r\nline 2");
+
+    assert!(multipart.next_part().unwrap().is_none());
+    assert!(multipart.next_part().unwrap().is_none());
+}
+
+#[test]
+fn http_multipart_enforces_limits() {
+    let content = "--b\r\n\r\n0123456789\r\n--b\r\n\r\n01234\r\n--b--\r\n";
+    let limits = MultipartLimits {
+        max_part_size: 5,
+        ..MultipartLimits::default()
+    };
+    let mut from = Cursor::new(content);
+    let mut multipart = Multipart::new(&mut from, "b", limits).unwrap();
+    let mut part = multipart.next_part().unwrap().unwrap();
+    let err = part.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let limits = MultipartLimits {
+        max_size: 20,
+        ..MultipartLimits::default()
+    };
+    let mut from = Cursor::new(content);
+    let mut multipart = Multipart::new(&mut from, "b", limits).unwrap();
+    let err = multipart.next_part().err().unwrap();
+    assert_eq!(err, HttpError::BodyTooLarge);
+}
+
+#[test]
+fn http_multipart_rejects_invalid() {
+    let mut body = Body::from("--b\r\n\r\ncontent\r\n--b--", mime::TEXT_PLAIN);
+    let err = Multipart::from_body(&mut body, MultipartLimits::default()).err();
+    assert!(matches!(err, Some(HttpError::InvalidRequest(_))));
+
+    let content = "--b\r\n\r\ncontent without close delimiter";
+    let mut from = Cursor::new(content);
+    let mut multipart = Multipart::new(&mut from, "b", MultipartLimits::default()).unwrap();
+    let mut part = multipart.next_part().unwrap().unwrap();
+    let err = part.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    let err = multipart.next_part().err().unwrap();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+
+    let content = "--b\r\n\r\ncontent\r\n--bX\r\n";
+    let mut from = Cursor::new(content);
+    let mut multipart = Multipart::new(&mut from, "b", MultipartLimits::default()).unwrap();
+    multipart.next_part().unwrap().unwrap();
+    let err = multipart.next_part().err().unwrap();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+}
+
+#[test]
+fn http_request_form() {
+    let str_req = "POST /?a=1&b=x+y&a=2 HTTP/1.1\r\n\
+        Content-Type: application/x-www-form-urlencoded\r\n\
+        Content-Length: 18\r\n\r\n\
+        c=%26&d=&c=2&e=%3D";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    assert_eq!(req.query()["a"], vec!["1", "2"]);
+    assert_eq!(req.query()["b"], vec!["x y"]);
+    let form = req.form().unwrap();
+    assert_eq!(form.len(), 3);
+    assert_eq!(form["c"], vec!["&", "2"]);
+    assert_eq!(form["d"], vec![""]);
+    assert_eq!(form["e"], vec!["="]);
+
+    let mut req = Request::read_from_str("POST / HTTP/1.1\r\n\r\n").unwrap();
+    assert!(req.form().unwrap().is_empty());
+
+    let str_req = "POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\na=%zz";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    assert!(matches!(req.form(), Err(HttpError::InvalidRequest(_))));
+
+    let str_req = "POST / HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 3\r\n\r\na=b";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    assert!(matches!(req.form(), Err(HttpError::InvalidRequest(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn http_request_form_as() {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+        page: Option<u32>,
+    }
+
+    let req = Request::read_from_str("GET /?q=a+b&page=2 HTTP/1.1\r\n\r\n").unwrap();
+    let search: Search = req.query_as().unwrap();
+    assert_eq!(
+        search,
+        Search {
+            q: String::from("a b"),
+            page: Some(2)
+        }
+    );
+
+    let req = Request::read_from_str("GET /?q=a&page=x HTTP/1.1\r\n\r\n").unwrap();
+    let err = req.query_as::<Search>().unwrap_err();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+
+    let str_req = "POST / HTTP/1.1\r\n\
+        Content-Type: application/x-www-form-urlencoded\r\n\
+        Content-Length: 3\r\n\r\n\
+        q=b";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    let search: Search = req.form_as().unwrap();
+    assert_eq!(search.q, "b");
+    assert_eq!(search.page, None);
+
+    let str_req = "POST / HTTP/1.1\r\n\
+        Content-Type: application/x-www-form-urlencoded\r\n\
+        Content-Length: 6\r\n\r\n\
+        page=1";
+    let mut req = Request::read_from_str(str_req).unwrap();
+    let err = req.form_as::<Search>().unwrap_err();
+    assert!(matches!(err, HttpError::InvalidRequest(_)));
+}
+
+#[test]
+fn http_negotiate_media_type() {
+    use negotiation::negotiate;
+
+    let supported = [mime::APPLICATION_JSON, mime::TEXT_CSV, mime::TEXT_HTML];
+    let negotiate_with = |accept: &str| {
+        let mut headers = Headers::new();
+        headers.add(Header::new("Accept", accept));
+        negotiate(&headers, &supported)
+    };
+    assert_eq!(
+        negotiate(&Headers::new(), &supported),
+        Ok(mime::APPLICATION_JSON)
+    );
+    assert_eq!(negotiate_with("*/*"), Ok(mime::APPLICATION_JSON));
+    assert_eq!(negotiate_with("text/*, */*;q=0.1"), Ok(mime::TEXT_CSV));
+    assert_eq!(
+        negotiate_with("text/*;q=0.5, text/html, application/json;q=0.4"),
+        Ok(mime::TEXT_HTML)
+    );
+    // The most specific range wins.
+    assert_eq!(negotiate_with("text/*, text/csv;q=0"), Ok(mime::TEXT_HTML));
+    assert_eq!(
+        negotiate_with("text/html;charset=utf-8, text/csv;q=0.2"),
+        Ok(mime::TEXT_CSV)
+    );
+    // Elements with invalid weights are ignored.
+    assert_eq!(
+        negotiate_with("text/html;q=2, text/csv;q=0.1234, application/json;q=0.001"),
+        Ok(mime::APPLICATION_JSON)
+    );
+    assert_eq!(
+        negotiate_with("image/png, */*;q=0"),
+        Err(StatusCode::NotAcceptable)
+    );
+}
+
+#[test]
+fn http_negotiate_language_and_encoding() {
+    use negotiation::{negotiate_encoding, negotiate_language};
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Language", "en-GB, en;q=0.8, *;q=0.1"));
+    let language = negotiate_language(&headers, &["ca", "en-US", "en-GB"]);
+    assert_eq!(language, Ok(String::from("en-GB")));
+    let language = negotiate_language(&headers, &["ca", "EN-us"]);
+    assert_eq!(language, Ok(String::from("EN-us")));
+    let language = negotiate_language(&headers, &["ca"]);
+    assert_eq!(language, Ok(String::from("ca")));
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Language", "en-US"));
+    let language = negotiate_language(&headers, &["en", "ca"]);
+    assert_eq!(language, Err(StatusCode::NotAcceptable));
+    // The ranges are not matched in the middle of a multibyte character.
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Language", "e"));
+    let language = negotiate_language(&headers, &["éa"]);
+    assert_eq!(language, Err(StatusCode::NotAcceptable));
+
+    let supported = ["gzip", "identity"];
+    let encoding = negotiate_encoding(&Headers::new(), &supported);
+    assert_eq!(encoding, Ok(String::from("identity")));
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Encoding", "br"));
+    let encoding = negotiate_encoding(&headers, &supported);
+    assert_eq!(encoding, Ok(String::from("identity")));
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Encoding", "br, *;q=0.5"));
+    let encoding = negotiate_encoding(&headers, &supported);
+    assert_eq!(encoding, Ok(String::from("gzip")));
+    let mut headers = Headers::new();
+    headers.add(Header::new("Accept-Encoding", "br, identity;q=0"));
+    let encoding = negotiate_encoding(&headers, &supported);
+    assert_eq!(encoding, Err(StatusCode::NotAcceptable));
+}
+
+#[test]
+fn http_parse_range() {
+    use range::{parse_range, ByteRange};
+
+    let parse_with = |value: &str, length: u64| {
+        let mut headers = Headers::new();
+        headers.add(Header::new("Range", value));
+        parse_range(&headers, length)
+    };
+    let range = |start, end| ByteRange { start, end };
+    assert_eq!(parse_range(&Headers::new(), 10), Ok(None));
+    assert_eq!(parse_with("bytes=2-4", 10), Ok(Some(vec![range(2, 4)])));
+    assert_eq!(parse_with("bytes=2-", 10), Ok(Some(vec![range(2, 9)])));
+    assert_eq!(parse_with("bytes=5-100", 10), Ok(Some(vec![range(5, 9)])));
+    assert_eq!(parse_with("bytes=-3", 10), Ok(Some(vec![range(7, 9)])));
+    assert_eq!(parse_with("bytes=-30", 10), Ok(Some(vec![range(0, 9)])));
+    assert_eq!(
+        parse_with("bytes=6-8, 0-1, 10-12", 10),
+        Ok(Some(vec![range(0, 1), range(6, 8)]))
+    );
+    // Overlapping and adjacent ranges are coalesced.
+    assert_eq!(
+        parse_with("bytes=0-3, 2-5, 6-6", 10),
+        Ok(Some(vec![range(0, 6)]))
+    );
+    // Invalid ranges are ignored.
+    assert_eq!(parse_with("lines=1-2", 10), Ok(None));
+    assert_eq!(parse_with("bytes=4-2", 10), Ok(None));
+    assert_eq!(parse_with("bytes=a-2", 10), Ok(None));
+    assert_eq!(parse_with("bytes=+1-2", 10), Ok(None));
+    let too_many = vec!["0-0"; range::MAX_RANGES + 1].join(",");
+    assert_eq!(parse_with(&format!("bytes={}", too_many), 10), Ok(None));
+
+    let not_satisfiable = Err(StatusCode::RequestedRangeNotSatisfiable);
+    assert_eq!(parse_with("bytes=10-", 10), not_satisfiable);
+    assert_eq!(parse_with("bytes=-0", 10), not_satisfiable);
+    assert_eq!(parse_with("bytes=0-", 0), not_satisfiable);
+}
+
+#[test]
+fn http_range_if_range() {
+    use range::if_range_matches;
+
+    let etag = Some("\"1a-2b\"");
+    let last_modified = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT");
+    let matches = |value: &str| {
+        let mut headers = Headers::new();
+        headers.add(Header::new("If-Range", value));
+        if_range_matches(&headers, etag, last_modified)
+    };
+    assert!(if_range_matches(&Headers::new(), None, None));
+    assert!(matches("\"1a-2b\""));
+    assert!(!matches("W/\"1a-2b\""));
+    assert!(!matches("\"1a-2c\""));
+    assert!(matches("Sun, 06 Nov 1994 08:49:37 GMT"));
+    assert!(matches("Sunday, 06-Nov-94 08:49:37 GMT"));
+    assert!(!matches("Sun, 06 Nov 1994 08:49:38 GMT"));
+}
+
+#[test]
+fn http_range_partial_content() {
+    use range::{partial_content, ByteRange};
+
+    let content = Cursor::new("0123456789");
+    let ranges = [
+        ByteRange { start: 1, end: 2 },
+        ByteRange { start: 7, end: 9 },
+    ];
+    let response = partial_content(content, 10, mime::TEXT_PLAIN, &ranges);
+    assert_eq!(response.status, StatusCode::PartialContent);
+    let content_type = response.headers.content_type().unwrap();
+    assert_eq!(content_type.essence_str(), "multipart/byteranges");
+    let boundary = content_type.get_param(mime::BOUNDARY).unwrap();
+    let mut body = response.body.unwrap();
+    let mut content = Vec::new();
+    body.read_to_end(&mut content).unwrap();
+    assert_eq!(
+        response.headers.content_length(),
+        Some(content.len() as u64)
+    );
+
+    let mut from = Cursor::new(content);
+    let limits = MultipartLimits::default();
+    let mut multipart = Multipart::new(&mut from, boundary.as_str(), limits).unwrap();
+    let expected = [("bytes 1-2/10", "12"), ("bytes 7-9/10", "789")];
+    for (content_range, data) in expected {
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.content_type(), Some(mime::TEXT_PLAIN));
+        assert_eq!(part.headers.get_first("Content-Range"), Some(content_range));
+        let mut content = String::new();
+        part.read_to_string(&mut content).unwrap();
+        assert_eq!(content, data);
+    }
+    assert!(multipart.next_part().unwrap().is_none());
+}
+
+#[test]
+fn http_format_http_date() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    assert_eq!(
+        format_http_date(UNIX_EPOCH),
+        "Thu, 01 Jan 1970 00:00:00 GMT"
+    );
+    let time = UNIX_EPOCH + Duration::from_secs(951782400);
+    assert_eq!(format_http_date(time), "Tue, 29 Feb 2000 00:00:00 GMT");
+    let time = UNIX_EPOCH + Duration::from_secs(1703980799);
+    assert_eq!(format_http_date(time), "Sat, 30 Dec 2023 23:59:59 GMT");
+    let time = UNIX_EPOCH - Duration::from_secs(1);
+    assert_eq!(format_http_date(time), "Thu, 01 Jan 1970 00:00:00 GMT");
+    assert_eq!(http_date_now().len(), 29);
+}
+
+#[test]
+fn http_parse_http_date() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let time = UNIX_EPOCH + Duration::from_secs(951782400);
+    assert_eq!(parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT"), Some(time));
+    assert_eq!(
+        parse_http_date("Tuesday, 29-Feb-00 00:00:00 GMT"),
+        Some(time)
+    );
+    assert_eq!(parse_http_date("Tue Feb 29 00:00:00 2000"), Some(time));
+    let time = UNIX_EPOCH + Duration::from_secs(1703980799);
+    assert_eq!(parse_http_date(&format_http_date(time)), Some(time));
+    assert_eq!(
+        parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+        Some(UNIX_EPOCH)
+    );
+    assert_eq!(
+        parse_http_date("Thu, 01-Jan-70 00:00:00 GMT"),
+        Some(UNIX_EPOCH)
+    );
+
+    let invalid = [
+        "",
+        "Mon, 29 Feb 2001 00:00:00 GMT",
+        "Tue, 29 Feb 2000 24:00:00 GMT",
+        "Tue, 29 Feb 2000 00:00:00 UTC",
+        "Tue, 29 Foo 2000 00:00:00 GMT",
+        "Tue, 9 Feb 2000 00:00:00 GMT",
+        "Tue, 29 Feb 1969 00:00:00 GMT",
+        "Tue, 29 Feb 2000 0:00:00 GMT",
+        "Tue, 29 Feb +200 00:00:00 GMT",
+        "1994-11-06T08:49:37Z",
+    ];
+    for date in invalid {
+        assert_eq!(parse_http_date(date), None, "{}", date);
+    }
+}
+
+#[test]
+fn http_conditional_evaluate_preconditions() {
+    use conditional::evaluate_preconditions;
+    use std::time::Duration;
+
+    let last_modified = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT");
+    let earlier = "Sun, 06 Nov 1994 08:49:36 GMT";
+    let later = "Sun, 06 Nov 1994 08:49:38 GMT";
+    let evaluate = |method: &str, headers: &[(&str, &str)]| {
+        let mut request =
+            Request::read_from_str(&format!("{} / HTTP/1.1\r\n\r\n", method)).unwrap();
+        for (name, value) in headers {
+            request.headers.add(Header::new(name, value));
+        }
+        // The sub-second part of the modification time is ignored.
+        let last_modified = last_modified.map(|time| time + Duration::from_millis(500));
+        evaluate_preconditions(&request, Some("\"a,1\""), last_modified)
+    };
+    let not_modified = Some(StatusCode::NotModified);
+    let failed = Some(StatusCode::PreconditionFailed);
+
+    assert_eq!(evaluate("GET", &[]), None);
+    assert_eq!(
+        evaluate("GET", &[("If-None-Match", "\"b\", W/\"a,1\"")]),
+        not_modified
+    );
+    assert_eq!(evaluate("HEAD", &[("If-None-Match", "*")]), not_modified);
+    assert_eq!(evaluate("GET", &[("If-None-Match", "\"b\"")]), None);
+    assert_eq!(evaluate("PUT", &[("If-None-Match", "*")]), failed);
+    assert_eq!(
+        evaluate("GET", &[("If-Modified-Since", later)]),
+        not_modified
+    );
+    assert_eq!(evaluate("GET", &[("If-Modified-Since", earlier)]), None);
+    assert_eq!(evaluate("GET", &[("If-Modified-Since", "yesterday")]), None);
+    assert_eq!(evaluate("POST", &[("If-Modified-Since", later)]), None);
+    // If-None-Match takes precedence over If-Modified-Since.
+    let headers = [("If-None-Match", "\"b\""), ("If-Modified-Since", later)];
+    assert_eq!(evaluate("GET", &headers), None);
+
+    assert_eq!(evaluate("PUT", &[("If-Match", "\"a,1\"")]), None);
+    assert_eq!(evaluate("PUT", &[("If-Match", "*")]), None);
+    assert_eq!(evaluate("PUT", &[("If-Match", "W/\"a,1\"")]), failed);
+    assert_eq!(evaluate("PUT", &[("If-Unmodified-Since", earlier)]), failed);
+    assert_eq!(evaluate("PUT", &[("If-Unmodified-Since", later)]), None);
+    // If-Match takes precedence over If-Unmodified-Since.
+    let headers = [("If-Match", "\"a,1\""), ("If-Unmodified-Since", earlier)];
+    assert_eq!(evaluate("PUT", &headers), None);
+}
+
+#[test]
+fn http_headers_case_insensitive() {
+    let header_content = "content-length: 4\r\nX-CUSTOM: a\r\nx-custom: b\r\n\r\n";
+    let stream = &mut BufReader::new(header_content.as_bytes());
+    let headers = Headers::read_from(stream).unwrap();
+    assert_eq!(headers.get("Content-Length"), Some(&vec!["4".to_string()]));
+    assert_eq!(headers.get("CONTENT-LENGTH"), Some(&vec!["4".to_string()]));
+    assert_eq!(
+        headers.get("x-custom"),
+        Some(&vec!["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(headers.len(), 2);
+    assert!(headers.contains("X-Custom"));
+    assert!(!headers.contains("Host"));
+}
+
+#[test]
+fn http_headers_preserve_order() {
+    let mut headers = Headers::new();
+    let names = ["Zeta", "Alpha", "Mid", "Beta", "Omega"];
+    for name in names {
+        headers.add(Header::new(name, "value"));
+    }
+    headers.add(Header::new("alpha", "other"));
+    let mut to: Vec<u8> = Vec::new();
+    headers.write(&mut to).unwrap();
+    let got = String::from_utf8(to).unwrap();
+    let want = "Zeta: value\r\nAlpha: value\r\nAlpha: other\r\nMid: value\r\n\
+Beta: value\r\nOmega: value\r\n\r\n";
+    assert_eq!(got, want);
+}
+
+#[test]
+fn http_headers_set_and_remove() {
+    let mut headers = Headers::new();
+    headers.add(Header::new("A", "1"));
+    headers.add(Header::new("B", "2"));
+    headers.add(Header::new("B", "3"));
+    headers.add(Header::new("C", "4"));
+    headers.set(Header::new("b", "5"));
+    let got = headers
+        .iter()
+        .map(|(name, values)| (name.as_str(), values.join(",")))
+        .collect::<Vec<(&str, String)>>();
+    assert_eq!(
+        got,
+        vec![
+            ("A", "1".to_string()),
+            ("B", "5".to_string()),
+            ("C", "4".to_string())
+        ]
+    );
+    assert_eq!(headers.remove("a"), Some(vec!["1".to_string()]));
+    assert_eq!(headers.remove("a"), None);
+    assert_eq!(headers.get_first("B"), Some("5"));
+    assert_eq!(headers.len(), 2);
+}
+
+#[test]
+fn http_headers_typed_accessors() {
+    let mut headers = Headers::new();
+    assert_eq!(headers.content_length(), None);
+    assert_eq!(headers.content_type(), None);
+    assert_eq!(headers.host(), None);
+    assert!(headers.connection().is_empty());
+
+    headers.set_content_length(42);
+    headers.set_content_type(&mime::APPLICATION_JSON);
+    headers.set_host("example.com:8080");
+    headers.set_connection("Keep-Alive");
+    headers.set_accept("text/html, application/json;q=0.9");
+    headers.add(Header::new("Accept", "*/*;q=0.1"));
+    headers.set_cache_control("no-cache, max-age=0");
+    headers.set_date("Sun, 06 Nov 1994 08:49:37 GMT");
+
+    assert_eq!(headers.content_length(), Some(42));
+    assert_eq!(headers.content_type(), Some(mime::APPLICATION_JSON));
+    assert_eq!(headers.host(), Some("example.com:8080"));
+    assert_eq!(headers.connection(), vec!["keep-alive"]);
+    assert_eq!(
+        headers.accept(),
+        vec!["text/html", "application/json;q=0.9", "*/*;q=0.1"]
+    );
+    assert_eq!(headers.cache_control(), vec!["no-cache", "max-age=0"]);
+    assert_eq!(headers.date(), Some("Sun, 06 Nov 1994 08:49:37 GMT"));
+
+    headers.set(Header::new("content-length", "invalid"));
+    assert_eq!(headers.content_length(), None);
+}
+
+#[test]
+fn http_request_read_enforces_limits() {
+    let limits = Limits {
+        max_request_line_length: 32,
+        max_header_line_length: 32,
+        max_headers: 2,
+        max_headers_size: 64,
+        max_body_size: 4,
+    };
+    let read = |req: &str| Request::read_from_with_limits(Cursor::new(req.to_string()), &limits);
+
+    let req = read("GET /a HTTP/1.1\r\nHost: a\r\nContent-Length: 4\r\n\r\ntest").unwrap();
+    assert_eq!(req.uri.path(), "/a");
+
+    let err = read("GET /a/very/long/path/for/the/limit HTTP/1.1\r\n\r\n").unwrap_err();
+    assert_eq!(err, HttpError::RequestLineTooLong);
+    let err = read("GETGETGETGETGETGETGETGETGETGETGETGET / HTTP/1.1\r\n\r\n").unwrap_err();
+    assert_eq!(err, HttpError::RequestLineTooLong);
+    // The limit includes the CRLF at the end of the line.
+    assert!(read("GET /a/path/of/length HTTP/1.1\r\n\r\n").is_ok());
+    let err = read("GET /a/path/of/lengths HTTP/1.1\r\n\r\n").unwrap_err();
+    assert_eq!(err, HttpError::RequestLineTooLong);
+
+    let err = read("GET / HTTP/1.1\r\nHeader: a-very-long-header-value\r\n\r\n").unwrap_err();
+    assert_eq!(err, HttpError::HeadersTooLarge);
+    let err = read("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n").unwrap_err();
+    assert_eq!(err, HttpError::HeadersTooLarge);
+    let value = "a".repeat(27);
+    let req = format!("GET / HTTP/1.1\r\nA: {}\r\nB: {}\r\n\r\n", value, value);
+    let err = read(&req).unwrap_err();
+    assert_eq!(err, HttpError::HeadersTooLarge);
+
+    let err = read("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\ntests").unwrap_err();
+    assert_eq!(err, HttpError::BodyTooLarge);
+
+    let req = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\ntests\r\n0\r\n\r\n";
+    let mut body = read(req).unwrap().body.unwrap();
+    let mut content = String::new();
+    let err = body.read_to_string(&mut content).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
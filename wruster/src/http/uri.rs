@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use super::errors::HttpError;
+use super::errors::HttpError::InvalidRequest;
+
+/**
+Represents the request-target of an HTTP request as defined in:
+<https://datatracker.ietf.org/doc/html/rfc7230#section-5.3>.
+
+The path of the uri is percent-decoded and normalized, that is: the dot
+segments and the empty segments are removed, but the trailing slash, if any,
+is kept. The uris containing a fragment, a path that goes above the root or
+percent-encoded dot segments, like ``%2e%2e``, are rejected.
+
+# Examples
+
+```
+use wruster::http::Uri;
+
+let uri: Uri = "/a/./b/%C3%A9?x=1&x=2&y=a+b".parse().unwrap();
+assert_eq!(uri.path(), "/a/b/é");
+assert_eq!(uri.raw_path(), "/a/./b/%C3%A9");
+assert_eq!(uri.query(), Some("x=1&x=2&y=a+b"));
+assert_eq!(uri.query_param("y"), Some("a b"));
+assert_eq!(
+    uri.query_params().get("x"),
+    Some(&vec!["1".to_string(), "2".to_string()])
+);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    raw: String,
+    raw_path: String,
+    path: String,
+    query: Option<String>,
+    query_params: HashMap<String, Vec<String>>,
+}
+
+impl Uri {
+    /**
+    Parses a request-target in origin-form: ``/path?query``, absolute-form:
+    ``http://host/path?query`` or asterisk-form: ``*``.
+
+    # Errors
+
+    Returns an [`HttpError::InvalidRequest`] if the uri is not valid, contains
+    a fragment, contains percent-encoded dot segments or its path goes above
+    the root.
+    */
+    pub fn parse(raw: &str) -> Result<Uri, HttpError> {
+        if raw.is_empty() {
+            return Err(InvalidRequest(String::from("empty uri")));
+        }
+        if raw.contains('#') {
+            return Err(InvalidRequest(format!("uri with fragment {}", raw)));
+        }
+        if raw.bytes().any(|c| c <= b' ' || c == 0x7f) {
+            return Err(InvalidRequest(format!("invalid uri {}", raw)));
+        }
+        if raw == "*" {
+            return Ok(Uri {
+                raw: raw.to_string(),
+                raw_path: raw.to_string(),
+                path: raw.to_string(),
+                query: None,
+                query_params: HashMap::new(),
+            });
+        }
+        let (target, query) = match raw.split_once('?') {
+            None => (raw, None),
+            Some((target, query)) => (target, Some(query)),
+        };
+        let raw_path = match target.find("://") {
+            None => target,
+            Some(pos) => {
+                // absolute-form: skip the scheme and the authority.
+                let authority = &target[pos + 3..];
+                match authority.find('/') {
+                    None => "/",
+                    Some(pos) => &authority[pos..],
+                }
+            }
+        };
+        let path = decode_path(raw_path)?;
+        let query_params = match query {
+            None => HashMap::new(),
+            Some(query) => parse_query(query)?,
+        };
+        Ok(Uri {
+            raw: raw.to_string(),
+            raw_path: raw_path.to_string(),
+            path,
+            query: query.map(String::from),
+            query_params,
+        })
+    }
+
+    // Creates a uri without validating it, the path of the uri is the raw
+    // uri. It's used when building requests to be sent, where the uri is
+    // only written back as is.
+    pub(crate) fn unchecked(raw: &str) -> Uri {
+        Uri::parse(raw).unwrap_or_else(|_| Uri {
+            raw: raw.to_string(),
+            raw_path: raw.to_string(),
+            path: raw.to_string(),
+            query: None,
+            query_params: HashMap::new(),
+        })
+    }
+
+    /// Returns the uri as it was received.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Returns the percent-decoded and normalized path of the uri.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the path of the uri as it was received, without decoding it.
+    pub fn raw_path(&self) -> &str {
+        &self.raw_path
+    }
+
+    /// Returns the query of the uri, if any, without decoding it.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// Returns the decoded parameters of the query of the uri indexed by
+    /// name. A parameter can have several values.
+    pub fn query_params(&self) -> &HashMap<String, Vec<String>> {
+        &self.query_params
+    }
+
+    /// Returns the first value of the query parameter with the given name, if
+    /// any.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params
+            .get(name)
+            .and_then(|values| values.first())
+            .map(|value| value.as_str())
+    }
+}
+
+impl FromStr for Uri {
+    type Err = HttpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uri::parse(s)
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl Default for Uri {
+    fn default() -> Self {
+        Uri::parse("/").unwrap()
+    }
+}
+
+fn decode_path(raw_path: &str) -> Result<String, HttpError> {
+    if !raw_path.starts_with('/') {
+        return Err(InvalidRequest(format!("invalid uri path {}", raw_path)));
+    }
+    let mut segments: Vec<String> = Vec::new();
+    let mut trailing_slash = false;
+    for raw_segment in raw_path[1..].split('/') {
+        let decoded = percent_decode(raw_segment, false)?;
+        if decoded.contains(&0) {
+            return Err(InvalidRequest(format!("invalid uri path {}", raw_path)));
+        }
+        let decoded = String::from_utf8(decoded)
+            .map_err(|_| InvalidRequest(format!("invalid uri path {}", raw_path)))?;
+        let encoded = raw_segment.contains('%');
+        // An encoded slash splits the segment in several ones.
+        for segment in decoded.split('/') {
+            trailing_slash = matches!(segment, "" | "." | "..");
+            match segment {
+                "." | ".." if encoded => {
+                    let msg = format!("encoded dot segment in uri path {}", raw_path);
+                    return Err(InvalidRequest(msg));
+                }
+                "" | "." => continue,
+                ".." => {
+                    if segments.pop().is_none() {
+                        let msg = format!("uri path above the root {}", raw_path);
+                        return Err(InvalidRequest(msg));
+                    }
+                }
+                segment => segments.push(segment.to_string()),
+            }
+        }
+    }
+    let mut path = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        path.push('/');
+    }
+    Ok(path)
+}
+
+/**
+Decodes a string in the ``application/x-www-form-urlencoded`` format, used by
+the query strings and the bodies of the HTML forms:
+<https://url.spec.whatwg.org/#application/x-www-form-urlencoded>, into a map
+that contains all the values of each name.
+*/
+pub(crate) fn parse_query(query: &str) -> Result<HashMap<String, Vec<String>>, HttpError> {
+    let mut params: HashMap<String, Vec<String>> = HashMap::new();
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+        let name = String::from_utf8_lossy(&percent_decode(name, true)?).to_string();
+        let value = String::from_utf8_lossy(&percent_decode(value, true)?).to_string();
+        params.entry(name).or_default().push(value);
+    }
+    Ok(params)
+}
+
+/**
+Decodes a percent-encoded string as specified in:
+<https://datatracker.ietf.org/doc/html/rfc3986#section-2.1>, if
+``plus_as_space`` is true the ``+`` chars are decoded as spaces.
+*/
+pub(crate) fn percent_decode(encoded: &str, plus_as_space: bool) -> Result<Vec<u8>, HttpError> {
+    let encoded = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        match encoded[i] {
+            b'%' => {
+                let hex = encoded
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(c) => decoded.push(c),
+                    None => {
+                        let msg = String::from("invalid percent-encoded sequence");
+                        return Err(InvalidRequest(msg));
+                    }
+                }
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            c => {
+                decoded.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(decoded)
+}
@@ -24,7 +24,7 @@ impl fmt::Display for ParseVersionError {
 /**
 Represents the HTTP versions considered valid.
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Version {
     /** HTTP version 1.0*/
     HTTP1_0,
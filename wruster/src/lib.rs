@@ -1,5 +1,21 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::broken_intra_doc_links)]
+// The existing unit tests predate these lints, so they are allowed in them.
+#![cfg_attr(
+    test,
+    allow(
+        clippy::bool_assert_comparison,
+        clippy::borrow_deref_ref,
+        clippy::clone_on_copy,
+        clippy::let_unit_value,
+        clippy::needless_borrow,
+        clippy::redundant_field_names,
+        clippy::to_string_in_format_args,
+        clippy::unit_cmp,
+        clippy::unused_io_amount,
+        clippy::useless_conversion
+    )
+)]
 
 /*!
 Experimental simple web sever that includes a http
@@ -56,13 +72,15 @@ use std::{net, thread};
 #[macro_use]
 extern crate log;
 use http::errors::HttpError;
+use http::headers::Headers;
 use http::*;
 use polling::{Event, Poller};
-use router::{Normalize, Router};
+use router::{Normalize, RouteError, Router};
 pub use streams::tls::{Certificate, PrivateKey};
 use streams::{
     cancellable_stream::CancellableStream,
     observable::{ObservedStream, ObservedStreamList},
+    shared_reader::SharedReader,
     timeout_stream::TimeoutStream,
     tls, Stream,
 };
@@ -103,6 +121,8 @@ pub struct Server {
     handle: Option<JoinHandle<Result<(), Box<Error>>>>,
     poller: Option<Arc<Poller>>,
     timeouts: Timeouts,
+    limits: Limits,
+    default_headers: Headers,
 }
 
 impl Server {
@@ -133,6 +153,8 @@ impl Server {
             handle,
             poller,
             timeouts,
+            limits: Limits::default(),
+            default_headers: Headers::new(),
         }
     }
 
@@ -165,9 +187,58 @@ impl Server {
             handle,
             poller,
             timeouts,
+            limits: Limits::default(),
+            default_headers: Headers::new(),
         }
     }
 
+    /**
+    Sets the [Limits] enforced when reading the requests, by default the
+    server uses [`Limits::default`]. The requests exceeding the limits are
+    answered with a 414, 431 or 413 status code.
+
+    # Examples
+
+    ```
+    use wruster::http::Limits;
+    use wruster::Server;
+
+    let limits = Limits {
+        max_body_size: 1024 * 1024,
+        ..Limits::default()
+    };
+    let server = Server::new().with_limits(limits);
+    ```
+    */
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /**
+    Sets the headers added to every response, like ``Server`` or
+    ``Strict-Transport-Security``, unless the handler of the request already
+    set a header with the same name. By default no headers are added, apart
+    from the ``Date`` header that every response gets if it doesn't have one:
+    <https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.2>.
+
+    # Examples
+
+    ```
+    use wruster::http::headers::{Header, Headers};
+    use wruster::Server;
+
+    let mut headers = Headers::new();
+    headers.add(Header::new("Server", "wruster"));
+    headers.add(Header::new("Strict-Transport-Security", "max-age=31536000"));
+    let server = Server::new().with_default_headers(headers);
+    ```
+    */
+    pub fn with_default_headers(mut self, headers: Headers) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
     /**
     Starts a server listening on the specified address and using the given
     [`Router`], it returns the control immediately to caller.
@@ -309,8 +380,7 @@ impl Server {
                 let default_value = 2;
                 error!(
                     "error getting available run units: {}, using default value: {}",
-                    err.to_string(),
-                    default_value
+                    err, default_value
                 );
                 default_value
             }
@@ -318,10 +388,14 @@ impl Server {
 
         let stop = Arc::clone(&self.stop);
         let timeouts = self.timeouts.clone();
+        let limits = self.limits;
+        let default_headers = Arc::new(self.default_headers.clone());
 
         let handle = thread::spawn(move || {
             Self::accept_connections(
                 timeouts,
+                limits,
+                default_headers,
                 stop,
                 listener,
                 execunits,
@@ -336,8 +410,11 @@ impl Server {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn accept_connections<F, T: Stream + Send + Sync + 'static>(
         timeouts: Timeouts,
+        limits: Limits,
+        default_headers: Arc<Headers>,
         stop: Arc<AtomicBool>,
         listener: TcpListener,
         execunits: usize,
@@ -367,24 +444,37 @@ impl Server {
                 info!("accepting connection from {}", src_addr);
                 let cconfig = Arc::clone(&routes);
                 let action_timeouts = timeouts.clone();
+                let action_headers = Arc::clone(&default_headers);
 
                 let stream = stream_builder(stream);
                 let action_stream = match stream {
                     Ok(stream) => stream,
                     Err(err) => {
-                        error!("error cloning stream: {}", err.to_string());
+                        error!("error cloning stream: {}", err);
                         continue;
                     }
                 };
                 let action_stream = ObservedStreamList::track(&active_streams, action_stream);
                 let local_action_stream = action_stream.clone();
                 let action = move || {
-                    handle_conversation(action_stream, cconfig, action_timeouts.clone(), src_addr);
+                    handle_conversation(
+                        action_stream,
+                        cconfig,
+                        action_timeouts.clone(),
+                        limits,
+                        action_headers,
+                        src_addr,
+                    );
                 };
 
                 if pool.run(Box::new(action)).is_err() {
                     error!("server too busy to handle connection with: {}", src_addr);
-                    handle_busy(local_action_stream, timeouts.clone(), src_addr);
+                    handle_busy(
+                        local_action_stream,
+                        timeouts.clone(),
+                        &default_headers,
+                        src_addr,
+                    );
                 }
             }
             if stop.as_ref().load(Ordering::SeqCst) {
@@ -522,8 +612,12 @@ impl Default for Server {
     }
 }
 
-fn handle_busy<T>(stream: ObservedStream<T>, timeouts: Timeouts, src_addr: SocketAddr)
-where
+fn handle_busy<T>(
+    stream: ObservedStream<T>,
+    timeouts: Timeouts,
+    default_headers: &Headers,
+    src_addr: SocketAddr,
+) where
     T: Stream,
 {
     debug!("sending too busy to {}", src_addr);
@@ -532,8 +626,9 @@ where
     let shutdown_stream = stream.clone();
     let mut timeout_stream = TimeoutStream::from(stream, read_timeout, write_timeout);
     let mut resp = Response::from_status(StatusCode::ServiceUnavailable);
+    add_server_headers(&mut resp, default_headers);
     if let Err(err) = resp.write(&mut timeout_stream) {
-        error!("sending too busy to {}: {}", src_addr, err.to_string())
+        error!("sending too busy to {}: {}", src_addr, err)
     }
     let s = &*shutdown_stream;
     if let Err(err) = s.shutdown(net::Shutdown::Both) {
@@ -546,19 +641,30 @@ fn handle_conversation<T>(
     mut stream: ObservedStream<T>,
     routes: Arc<Router>,
     timeouts: Timeouts,
+    limits: Limits,
+    default_headers: Arc<Headers>,
     source_addr: SocketAddr,
 ) where
     T: Stream + 'static,
 {
     debug!("handling conversation with {}", source_addr);
+    // The requests in the connection are read using the same buffered reader,
+    // so the bytes of pipelined requests read ahead are not lost.
+    let read_timeout = Some(timeouts.read_request_timeout);
+    let write_timeout = Some(timeouts.write_response_timeout);
+    let read_stream = TimeoutStream::from(stream.clone(), read_timeout, write_timeout);
+    let reader = SharedReader::new(read_stream);
     let mut connection_open = true;
     while connection_open {
         let handle_stream = stream.clone();
         connection_open = handle_connection(
             handle_stream,
+            &reader,
             Arc::clone(&routes),
             source_addr,
             timeouts.clone(),
+            &limits,
+            &default_headers,
         );
         if let Err(err) = stream.flush() {
             error!("error flushing to: {}, {}", source_addr, err);
@@ -575,24 +681,62 @@ fn handle_conversation<T>(
 
 fn handle_connection<T>(
     stream: ObservedStream<T>,
+    reader: &SharedReader<TimeoutStream<ObservedStream<T>>>,
     routes: Arc<Router>,
     source_addr: SocketAddr,
     timeouts: Timeouts,
+    limits: &Limits,
+    default_headers: &Headers,
 ) -> bool
 where
     T: Stream + 'static,
 {
-    let connection_open: bool;
+    let mut connection_open: bool;
     let read_timeout = Some(timeouts.read_request_timeout);
     let write_timeout = Some(timeouts.write_response_timeout);
 
-    let resp_stream = stream.clone();
-    let timeout_stream = TimeoutStream::from(stream, read_timeout, write_timeout);
+    let mut from = match reader.lend() {
+        Some(from) => from,
+        None => {
+            error!(
+                "reader of the connection with {} already in use",
+                source_addr
+            );
+            return false;
+        }
+    };
+    // The timeout for reading a request starts when the server starts
+    // reading it.
+    from.get_mut().reset();
 
-    let (request, mut response) = match Request::read_from(timeout_stream) {
+    let mut is_head = false;
+    let mut continue_sent = None;
+    let (request, mut response) = match Request::read_from_buffered(from, limits) {
         Ok(mut request) => {
+            is_head = request.method == HttpMethod::HEAD;
             connection_open = is_connection_persistent(&request);
-            let response = run_action(&mut request, routes);
+            // The expectations of HTTP/1.0 requests must be ignored:
+            // https://datatracker.ietf.org/doc/html/rfc7231#section-5.1.1.
+            let is_http1_0 = request.version == Version::HTTP1_0;
+            let response = match request.headers.get_first("Expect") {
+                Some(expect) if !is_http1_0 && !request.expects_continue() => {
+                    debug!("unsupported expectation: {}", expect);
+                    connection_open = false;
+                    Response::from_status(StatusCode::ExpectationFailed)
+                }
+                _ => {
+                    // The 100 Continue interim response is sent when the
+                    // handler starts reading the body.
+                    if request.expects_continue() {
+                        if let Some(body) = request.body.as_mut() {
+                            let to =
+                                TimeoutStream::from(stream.clone(), read_timeout, write_timeout);
+                            continue_sent = Some(body.expect_continue(to));
+                        }
+                    }
+                    run_action(&mut request, routes)
+                }
+            };
             (Some(request), response)
         }
         Err(err) => match err {
@@ -602,6 +746,30 @@ where
                 let response = Response::from_status(StatusCode::BadRequest);
                 (None, response)
             }
+            HttpError::RequestLineTooLong => {
+                error!("error reading request from {}: {}", source_addr, err);
+                connection_open = false;
+                let response = Response::from_status(StatusCode::RequestURITooLarge);
+                (None, response)
+            }
+            HttpError::HeadersTooLarge => {
+                error!("error reading request from {}: {}", source_addr, err);
+                connection_open = false;
+                let response = Response::from_status(StatusCode::RequestHeaderFieldsTooLarge);
+                (None, response)
+            }
+            HttpError::BodyTooLarge => {
+                error!("error reading request from {}: {}", source_addr, err);
+                connection_open = false;
+                let response = Response::from_status(StatusCode::RequestEntityTooLarge);
+                (None, response)
+            }
+            HttpError::VersionNotSupported(_) => {
+                error!("error reading request from {}: {}", source_addr, err);
+                connection_open = false;
+                let response = Response::from_status(StatusCode::HTTPVersionNotSupported);
+                (None, response)
+            }
             err => {
                 debug!("error reading request {:?}", err);
                 return false;
@@ -609,20 +777,69 @@ where
         },
     };
 
+    // If the handler rejected a request expecting 100 Continue without reading
+    // its body, the client may not send the body, so the body is not read and
+    // the connection is closed after writing the response.
+    let body_rejected = continue_sent.map(|sent| !sent.get()).unwrap_or(false);
+    if body_rejected {
+        debug!(
+            "request body rejected, closing connection with {}",
+            source_addr
+        );
+        connection_open = false;
+    }
+
+    // The response is sent using the version of the request. A HTTP/1.0
+    // client must be told that the connection is kept open, and it can't
+    // decode a chunked body, so in that case the connection is closed to
+    // signal the end of the body.
+    if let Some(request) = request.as_ref() {
+        response.version = request.version;
+        if response.is_close_delimited() {
+            connection_open = false;
+        }
+        if connection_open && request.version == Version::HTTP1_0 {
+            response.headers.set_connection("keep-alive");
+        }
+    }
+
     // Ensure the request body (if any) is read.
-    if let Some(mut request) = request {
+    if let Some(mut request) = request.filter(|_| !body_rejected) {
         let body = request.body.as_mut();
         if let Some(body) = body {
-            if let Err(err) = body.ensure_read() {
-                error!("error reading request body, error info: {}", err);
-                return false;
+            match body.ensure_read() {
+                Ok(()) => (),
+                // The rest of the body is not read, so the connection can't
+                // be used for more requests.
+                Err(HttpError::BodyTooLarge) => {
+                    error!(
+                        "error reading request body from {}: request body too large",
+                        source_addr
+                    );
+                    connection_open = false;
+                    response = Response::from_status(StatusCode::RequestEntityTooLarge);
+                    response.version = request.version;
+                    response.headers.set_connection("close");
+                }
+                Err(err) => {
+                    error!("error reading request body, error info: {}", err);
+                    return false;
+                }
             }
         }
     }
 
+    add_server_headers(&mut response, default_headers);
+
     // Write the response.
-    let mut timeout_stream = TimeoutStream::from(resp_stream, read_timeout, write_timeout);
-    if let Err(err) = response.write(&mut timeout_stream) {
+    // The response to a HEAD request contains the same headers as the
+    // response to a GET, but never a body.
+    let mut timeout_stream = TimeoutStream::from(stream, read_timeout, write_timeout);
+    let written = match is_head {
+        true => response.write_head(&mut timeout_stream),
+        false => response.write(&mut timeout_stream),
+    };
+    if let Err(err) = written {
         error!(
             "error writing response to: {}, error info: {}",
             source_addr, err
@@ -633,7 +850,7 @@ where
 }
 
 fn run_action(request: &mut Request, routes: Arc<Router>) -> Response {
-    let req_path = PathBuf::from(request.uri.clone());
+    let req_path = PathBuf::from(request.uri.path());
     let normalized = match req_path.normalize() {
         Ok(path) => path,
         Err(err) => {
@@ -647,31 +864,68 @@ fn run_action(request: &mut Request, routes: Arc<Router>) -> Response {
         None => return Response::from_status(StatusCode::InternalServerError),
         Some(path) => path,
     };
-    let action = match routes.get_prefix(String::from(normalized), request.method) {
-        Some(action) => action,
-        None => return Response::from_status(StatusCode::NotFound),
+    let (action, params) = match routes.get_prefix(String::from(normalized), &request.method) {
+        Ok(route) => route,
+        Err(RouteError::NotFound) => return Response::from_status(StatusCode::NotFound),
+        Err(RouteError::MethodNotAllowed(allowed)) => {
+            return allowed_methods_response(&request.method, &allowed)
+        }
     };
-    request.uri = String::from(normalized);
+    request.params = params;
     action(request)
 }
 
+/**
+Builds the response for a request to a path that has no handler for the method
+of the request. The response contains an ``Allow`` header listing the methods
+the path has handlers for: <https://httpwg.org/specs/rfc7231.html#header.allow>.
+If the method of the request is OPTIONS, the response status is 200, otherwise
+is 405.
+*/
+fn allowed_methods_response(method: &HttpMethod, allowed: &[HttpMethod]) -> Response {
+    let status = match method {
+        HttpMethod::OPTIONS => StatusCode::OK,
+        _ => StatusCode::MethodNotAllowed,
+    };
+    let mut response = Response::from_status(status);
+    let allowed = allowed
+        .iter()
+        .map(HttpMethod::as_str)
+        .collect::<Vec<&str>>();
+    response.headers.add(headers::Header {
+        name: String::from("Allow"),
+        value: allowed.join(", "),
+    });
+    response
+}
+
+/**
+Adds to a response the default headers configured in the server, see
+[`Server::with_default_headers`], and the ``Date`` header, unless the response
+already contains them.
+*/
+fn add_server_headers(response: &mut Response, default_headers: &Headers) {
+    response.headers.add_missing(default_headers);
+    if !response.headers.contains("Date") {
+        response.headers.set_date(&http_date_now());
+    }
+}
+
 /**
 Evaluates if a request requires a connection to be [persistent](https://httpwg.org/specs/rfc7230.html#rfc.section.6.3).
 */
 fn is_connection_persistent(request: &http::Request) -> bool {
-    let value = match request.headers.get("Connection") {
-        None => "".to_string(),
-        Some(values) => values[0].to_lowercase(),
-    };
-    if value == "close" {
+    let connection = request.headers.connection();
+    if connection.iter().any(|option| option == "close") {
         return false;
     }
 
-    if request.version == "HTTP/1.1" || request.version == "HTTP/2" {
+    if request.version == Version::HTTP1_1 || request.version == Version::HTTP2 {
         return true;
     };
 
-    if request.version == "HTTP/1.0" && value == "keep-alive" {
+    if request.version == Version::HTTP1_0 && connection.iter().any(|option| option == "keep-alive")
+    {
         return true;
     };
     false
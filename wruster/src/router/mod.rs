@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path;
 use std::path::Component;
 use std::sync::Arc;
@@ -5,7 +6,8 @@ use std::sync::Arc;
 use atomic_refcell::AtomicRefCell;
 
 mod trie;
-use trie::Trie;
+pub use trie::MatchMode;
+use trie::{Params, Trie};
 
 use crate::http::{HttpMethod, Request, Response};
 
@@ -40,12 +42,77 @@ impl Router {
     /// the router will select the most concrete handler that is, at least,
     /// registered for a path that is parent of the request path. For instance:
     /// if a handler has been registered for GET's in the path "/a", a GET
-    /// request to the path "/a/b" the will be attended with that Handler.
+    /// request to the path "/a/b" the will be attended with that Handler, but
+    /// a request to the path "/ab" will not. Use [`Router::add_with_mode`] to
+    /// change how the path of the route matches the paths of the requests.
+    ///
+    /// The path of a route can contain parameters, a segment starting with
+    /// ``:``, and a wildcard, a last segment starting with ``*``. A parameter
+    /// matches any non empty segment of the request path, and a wildcard
+    /// matches the rest of the request path. The values matched are available
+    /// to the handler in the [`Request::params`] field, indexed by the name of
+    /// the parameter or wildcard. When more than one route matches a request,
+    /// a static segment wins over a parameter, and a parameter wins over a
+    /// wildcard.
     ///
     /// # Examples
-    /// TODO
     ///
+    /// ```
+    /// use std::str::FromStr;
+    ///
+    /// use wruster::http::{HttpMethod, Request, Response};
+    /// use wruster::router::{HttpHandler, Router};
+    ///
+    /// let router = Router::new();
+    /// let handler: HttpHandler = Box::new(|request: &mut Request| {
+    ///     let id = request.params.get("id").unwrap();
+    ///     let post = request.params.get("post").unwrap();
+    ///     Response::from_str(&format!("user {} post {}", id, post)).unwrap()
+    /// });
+    /// router.add("/users/:id/posts/:post", HttpMethod::GET, handler);
+    /// let handler: HttpHandler = Box::new(|request: &mut Request| {
+    ///     let path = request.params.get("path").unwrap();
+    ///     Response::from_str(path).unwrap()
+    /// });
+    /// router.add("/files/*path", HttpMethod::GET, handler);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route contains a parameter or a wildcard without a name,
+    /// a wildcard that is not in the last segment, or a parameter with a
+    /// different name than the one of a parameter already registered in the
+    /// same position.
     pub fn add(&self, route: &str, method: HttpMethod, action: HttpHandler) {
+        self.add_with_mode(route, method, action, MatchMode::default())
+    }
+
+    /// Adds a route in the same way than [`Router::add`] but specifying how
+    /// the path of the route matches the path of the requests, see
+    /// [`MatchMode`]. The mode applies to the path, so it's shared by all the
+    /// methods registered for it, and the last mode set for the path is the
+    /// one used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wruster::http::{HttpMethod, Request, Response, StatusCode};
+    /// use wruster::router::{HttpHandler, MatchMode, Router};
+    ///
+    /// let router = Router::new();
+    /// let handler: HttpHandler = Box::new(|_: &mut Request| {
+    ///     Response::from_status(StatusCode::OK)
+    /// });
+    /// // Only requests to the path "/health" will be attended.
+    /// router.add_with_mode("/health", HttpMethod::GET, handler, MatchMode::Exact);
+    /// ```
+    pub fn add_with_mode(
+        &self,
+        route: &str,
+        method: HttpMethod,
+        action: HttpHandler,
+        mode: MatchMode,
+    ) {
         // We prioritize keeping the code of the Trie simpler over adding the
         // routes faster.
         let mut routes = self.routes.borrow_mut();
@@ -53,27 +120,40 @@ impl Router {
             None => MethodHandlers::new(),
             Some(route_actions) => route_actions,
         };
-        router_handlers.actions.borrow_mut()[method as usize] = Some(Arc::new(action));
-        routes.add_value(route.as_bytes(), router_handlers);
+        router_handlers.set_action(method, Arc::new(action));
+        routes.add_value(route.as_bytes(), router_handlers, mode);
     }
 
     #[allow(dead_code)]
     fn get(&self, route: &str, method: HttpMethod) -> Option<Arc<HttpHandler>> {
         let routes = self.routes.borrow();
-        let method_actions = match routes.get_value(route.as_bytes()) {
-            None => return None,
-            Some(actions) => actions,
-        };
-        method_actions.get_action(method)
+        let method_actions = routes.get_value(route.as_bytes())?;
+        method_actions.get_action(&method)
     }
 
-    pub(crate) fn get_prefix(&self, route: String, method: HttpMethod) -> Option<Arc<HttpHandler>> {
+    pub(crate) fn get_prefix(
+        &self,
+        route: String,
+        method: &HttpMethod,
+    ) -> Result<(Arc<HttpHandler>, Params), RouteError> {
         let routes = self.routes.borrow();
-        let method_actions = match routes.get_value_prefix(route.as_bytes()) {
-            None => return None,
-            Some(actions) => actions,
+        let (method_actions, params) = routes
+            .get_value_prefix(route.as_bytes())
+            .ok_or(RouteError::NotFound)?;
+        // HEAD requests are handled by the GET handler when there is no
+        // specific handler for them.
+        let action = match method {
+            HttpMethod::HEAD => method_actions
+                .get_action(method)
+                .or_else(|| method_actions.get_action(&HttpMethod::GET)),
+            method => method_actions.get_action(method),
         };
-        method_actions.get_action(method)
+        match action {
+            Some(action) => Ok((action, params)),
+            None => Err(RouteError::MethodNotAllowed(
+                method_actions.allowed_methods(),
+            )),
+        }
     }
 }
 
@@ -83,26 +163,79 @@ impl Default for Router {
     }
 }
 
+/// Defines the reasons why a [`Router`] can't find a handler for a request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum RouteError {
+    /// There is no route for the path of the request.
+    NotFound,
+    /// There is a route for the path of the request, but not for its
+    /// method. It contains the methods the path has a handler for.
+    MethodNotAllowed(Vec<HttpMethod>),
+}
+
+// Holds the handlers of a route. The handlers for the standard methods are
+// indexed by the index of the method, and the ones for the extension methods
+// by the name of the method.
 pub(crate) struct MethodHandlers {
     actions: AtomicRefCell<Vec<Option<Arc<HttpHandler>>>>,
+    extensions: AtomicRefCell<BTreeMap<String, Arc<HttpHandler>>>,
 }
 
 impl MethodHandlers {
     fn new() -> MethodHandlers {
         let mut actions = Vec::<Option<Arc<HttpHandler>>>::new();
-        for _ in 0..HttpMethod::get_last() as usize + 1 {
+        for _ in 0..HttpMethod::STANDARD.len() {
             actions.push(None);
         }
         MethodHandlers {
             actions: AtomicRefCell::new(actions),
+            extensions: AtomicRefCell::new(BTreeMap::new()),
         }
     }
 
-    fn get_action(&self, method: HttpMethod) -> Option<Arc<HttpHandler>> {
+    fn set_action(&self, method: HttpMethod, action: Arc<HttpHandler>) {
+        match method.index() {
+            Some(index) => self.actions.borrow_mut()[index] = Some(action),
+            None => {
+                let mut extensions = self.extensions.borrow_mut();
+                extensions.insert(method.as_str().to_string(), action);
+            }
+        }
+    }
+
+    fn get_action(&self, method: &HttpMethod) -> Option<Arc<HttpHandler>> {
+        let action = match method.index() {
+            Some(index) => self.actions.borrow()[index].clone(),
+            None => self.extensions.borrow().get(method.as_str()).cloned(),
+        };
+        action
+    }
+
+    // Returns the methods with a handler, plus the OPTIONS method, as it is
+    // always answered by the server, and the HEAD method if there is a handler
+    // for GET.
+    fn allowed_methods(&self) -> Vec<HttpMethod> {
         let actions = self.actions.borrow();
-        actions[method as usize]
-            .as_ref()
-            .map(|action| Arc::clone(action))
+        let is_set = |method: &HttpMethod| {
+            method
+                .index()
+                .map(|index| actions[index].is_some())
+                .unwrap_or(false)
+        };
+        let extensions = self.extensions.borrow();
+        let extensions = extensions
+            .keys()
+            .map(|name| HttpMethod::Extension(name.clone()));
+        HttpMethod::STANDARD
+            .iter()
+            .filter(|method| match method {
+                HttpMethod::OPTIONS => true,
+                HttpMethod::HEAD => is_set(&HttpMethod::GET) || is_set(&HttpMethod::HEAD),
+                method => is_set(method),
+            })
+            .cloned()
+            .chain(extensions)
+            .collect()
     }
 }
 
@@ -147,10 +280,10 @@ impl Normalize for path::PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use std::{io::Cursor, path::PathBuf, str::FromStr};
+    use std::{collections::HashMap, io::Cursor, path::PathBuf, str::FromStr};
 
     use super::*;
-    use crate::http::{headers::Headers, Body};
+    use crate::http::{headers::Headers, status::StatusCode, Body, Version};
     #[test]
     fn normalizes_path() {
         // Returns error if the path is not absolute.
@@ -203,9 +336,10 @@ mod tests {
         let mut request = Request {
             body: Some(body),
             method: HttpMethod::POST,
-            uri: String::from("/"),
-            version: String::from("HTTP/1.1"),
+            uri: "/".parse().unwrap(),
+            version: Version::HTTP1_1,
             headers: Headers::new(),
+            params: HashMap::new(),
         };
         let resp = action(&mut request);
         let mut resp_body = resp.body.unwrap();
@@ -232,9 +366,10 @@ mod tests {
         let mut request = Request {
             body: None,
             method: HttpMethod::GET,
-            uri: String::from("/a/b"),
-            version: String::from("HTTP/1.1"),
+            uri: "/a/b".parse().unwrap(),
+            version: Version::HTTP1_1,
             headers: Headers::new(),
+            params: HashMap::new(),
         };
         let resp = action(&mut request);
         let mut resp_body = resp.body.unwrap();
@@ -248,9 +383,10 @@ mod tests {
         let mut request = Request {
             body: None,
             method: HttpMethod::GET,
-            uri: String::from("/a"),
-            version: String::from("HTTP/1.1"),
+            uri: "/a".parse().unwrap(),
+            version: Version::HTTP1_1,
             headers: Headers::new(),
+            params: HashMap::new(),
         };
         let resp = action(&mut request);
         let mut resp_body = resp.body.unwrap();
@@ -268,9 +404,143 @@ mod tests {
         routes.add("/a/b", HttpMethod::GET, action);
         let action: Box<dyn Fn(&mut Request) -> Response + Sync + Send> = Box::new(action_body);
         routes.add("/a/b", HttpMethod::POST, action);
-        _ = routes.get_prefix("/a/b".into(), HttpMethod::GET).unwrap();
+        _ = routes.get_prefix("/a/b".into(), &HttpMethod::GET).unwrap();
         _ = routes
-            .get_prefix("/a/b/c".into(), HttpMethod::POST)
+            .get_prefix("/a/b/c".into(), &HttpMethod::POST)
             .unwrap();
     }
+
+    #[test]
+    fn routes_params() {
+        let routes = Router::new();
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add("/users/:id/posts/:post", HttpMethod::GET, action);
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add("/users/*rest", HttpMethod::GET, action);
+
+        let (_, params) = routes
+            .get_prefix("/users/1/posts/2".into(), &HttpMethod::GET)
+            .unwrap();
+        assert_eq!(params.get("id").unwrap(), "1");
+        assert_eq!(params.get("post").unwrap(), "2");
+
+        let (_, params) = routes
+            .get_prefix("/users/1/comments".into(), &HttpMethod::GET)
+            .unwrap();
+        assert_eq!(params.get("rest").unwrap(), "1/comments");
+        assert_eq!(
+            routes
+                .get_prefix("/users/1/posts/2".into(), &HttpMethod::POST)
+                .err(),
+            Some(RouteError::MethodNotAllowed(vec![
+                HttpMethod::GET,
+                HttpMethod::HEAD,
+                HttpMethod::OPTIONS
+            ]))
+        );
+    }
+
+    #[test]
+    fn routes_head_falls_back_to_get() {
+        let routes = Router::new();
+        let action: HttpHandler = Box::new(|_: &mut Request| Response::from_status(StatusCode::OK));
+        routes.add("/a", HttpMethod::GET, action);
+        let action: HttpHandler = Box::new(|_: &mut Request| Response::from_status(StatusCode::OK));
+        routes.add("/b", HttpMethod::GET, action);
+        let action: HttpHandler =
+            Box::new(|_: &mut Request| Response::from_status(StatusCode::NoContent));
+        routes.add("/b", HttpMethod::HEAD, action);
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add("/c", HttpMethod::POST, action);
+
+        let mut request = Request {
+            body: None,
+            method: HttpMethod::HEAD,
+            uri: "/".parse().unwrap(),
+            version: Version::HTTP1_1,
+            headers: Headers::new(),
+            params: HashMap::new(),
+        };
+        let (action, _) = routes.get_prefix("/a".into(), &HttpMethod::HEAD).unwrap();
+        assert_eq!(action(&mut request).status, StatusCode::OK);
+
+        let (action, _) = routes.get_prefix("/b".into(), &HttpMethod::HEAD).unwrap();
+        assert_eq!(action(&mut request).status, StatusCode::NoContent);
+
+        assert_eq!(
+            routes.get_prefix("/c".into(), &HttpMethod::HEAD).err(),
+            Some(RouteError::MethodNotAllowed(vec![
+                HttpMethod::POST,
+                HttpMethod::OPTIONS
+            ]))
+        );
+    }
+
+    #[test]
+    fn routes_extension_methods() {
+        let routes = Router::new();
+        let propfind = HttpMethod::from_str("PROPFIND").unwrap();
+        let action: HttpHandler =
+            Box::new(|_: &mut Request| Response::from_status(StatusCode::Accepted));
+        routes.add("/dav", propfind.clone(), action);
+        let action: HttpHandler = Box::new(|_: &mut Request| Response::from_status(StatusCode::OK));
+        routes.add("/dav", HttpMethod::GET, action);
+
+        let mut request = Request {
+            body: None,
+            method: propfind.clone(),
+            uri: "/dav".parse().unwrap(),
+            version: Version::HTTP1_1,
+            headers: Headers::new(),
+            params: HashMap::new(),
+        };
+        let (action, _) = routes.get_prefix("/dav".into(), &propfind).unwrap();
+        assert_eq!(action(&mut request).status, StatusCode::Accepted);
+
+        // Parsing the name of a standard method returns the standard one.
+        let get = HttpMethod::from_str("GET").unwrap();
+        let (action, _) = routes.get_prefix("/dav".into(), &get).unwrap();
+        assert_eq!(action(&mut request).status, StatusCode::OK);
+
+        let mkcol = HttpMethod::from_str("MKCOL").unwrap();
+        assert_eq!(
+            routes.get_prefix("/dav".into(), &mkcol).err(),
+            Some(RouteError::MethodNotAllowed(vec![
+                HttpMethod::GET,
+                HttpMethod::HEAD,
+                HttpMethod::OPTIONS,
+                propfind
+            ]))
+        );
+    }
+
+    #[test]
+    fn routes_match_modes() {
+        let routes = Router::new();
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add("/api", HttpMethod::GET, action);
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add_with_mode("/health", HttpMethod::GET, action, MatchMode::Exact);
+        let action: HttpHandler = Box::new(|_: &mut Request| unimplemented!());
+        routes.add_with_mode("/static", HttpMethod::GET, action, MatchMode::BytePrefix);
+
+        assert!(routes.get_prefix("/api/a".into(), &HttpMethod::GET).is_ok());
+        assert!(routes
+            .get_prefix("/apixyz".into(), &HttpMethod::GET)
+            .is_err());
+        assert!(routes
+            .get_prefix("/api-internal".into(), &HttpMethod::GET)
+            .is_err());
+
+        assert!(routes
+            .get_prefix("/health".into(), &HttpMethod::GET)
+            .is_ok());
+        assert!(routes
+            .get_prefix("/health/a".into(), &HttpMethod::GET)
+            .is_err());
+
+        assert!(routes
+            .get_prefix("/static-v2".into(), &HttpMethod::GET)
+            .is_ok());
+    }
 }
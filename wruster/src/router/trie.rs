@@ -1,63 +1,140 @@
+use std::collections::HashMap;
+
+/// Holds the values captured by the parameters and the wildcards of a key
+/// when getting a value from a [`Trie`].
+pub type Params = HashMap<String, String>;
+
+/**
+Defines how the key of a value stored in a [`Trie`] matches the keys that it is
+a prefix of.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// The key only matches the keys that start with it followed by a ``/``,
+    /// e.g: ``/api`` matches ``/api/users`` but not ``/apixyz``.
+    #[default]
+    SegmentPrefix,
+    /// The key only matches keys equal to it.
+    Exact,
+    /// The key matches any key that starts with its bytes, e.g: ``/api``
+    /// matches ``/api/users`` and ``/apixyz``.
+    BytePrefix,
+}
+
+/// Stores values indexed by keys that can contain, apart from literal bytes,
+/// parameters and wildcards. A parameter starts with ``:`` at the beginning of
+/// a segment, a segment being the bytes between two ``/``, and matches any non
+/// empty segment, e.g: ``/users/:id``. A wildcard starts with ``*`` at the
+/// beginning of the last segment of a key and matches the rest of the key,
+/// e.g: ``/files/*path``.
 #[derive(Debug)]
 pub struct Trie<T> {
-    children: Vec<Option<Node<T>>>,
+    root: Node<T>,
 }
 
 impl<T> Trie<T> {
     pub fn new() -> Self {
-        let children = Node::empty_children();
-        Trie { children }
+        Trie { root: Node::new() }
     }
 
-    pub fn add_value(&mut self, key: &[u8], value: T) {
+    pub fn add_value(&mut self, key: &[u8], value: T, mode: MatchMode) {
         assert!(!key.is_empty());
-        Node::add_value_to_children(&mut self.children, key, value);
+        let tokens = Token::parse(key);
+        self.root.add_value(&tokens, value, mode);
     }
 
     pub fn get_value(&self, key: &[u8]) -> Option<&T> {
         if key.is_empty() {
             return None;
         }
-        let pos = key[0] as usize;
-        let children = &self.children;
-        let child = match &children[pos] {
-            None => return None,
-            Some(node) => node,
-        };
-        child.get_value(&key[1..])
+        let tokens = Token::parse(key);
+        self.root.get_value(&tokens)
     }
 
     pub fn move_value_out(&mut self, key: &[u8]) -> Option<T> {
         if key.is_empty() {
             return None;
         }
-        let pos = key[0] as usize;
-        let children = &mut self.children;
-        let child = match &mut children[pos] {
-            None => return None,
-            Some(node) => node,
-        };
-        child.move_value_out(&key[1..])
+        let tokens = Token::parse(key);
+        self.root.move_value_out(&tokens)
     }
 
-    pub fn get_value_prefix(&self, key: &[u8]) -> Option<&T> {
+    /// Returns the value whose key matches the given key, together with the
+    /// values captured by its parameters and wildcards. When more than one
+    /// key matches, the most specific is selected: a literal byte wins over a
+    /// parameter and a parameter wins over a wildcard. If no key matches, it
+    /// returns the value of the longest key that is a prefix of the given one
+    /// according to the [`MatchMode`] the value was added with.
+    pub fn get_value_prefix(&self, key: &[u8]) -> Option<(&T, Params)> {
         if key.is_empty() {
             return None;
         }
-        let pos = key[0] as usize;
-        let children = &self.children;
-        let child = match &children[pos] {
-            None => return None,
-            Some(node) => node,
-        };
-        child.get_value_prefix(&key[1..], None)
+        let mut params = Params::new();
+        if let Some(value) = self.root.find(key, true, false, &mut params) {
+            return Some((value, params));
+        }
+        let mut params = Params::new();
+        self.root
+            .find(key, true, true, &mut params)
+            .map(|value| (value, params))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Byte(u8),
+    Param(String),
+    Wildcard(String),
+}
+
+impl Token {
+    fn parse(key: &[u8]) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < key.len() {
+            let segment_start = i == 0 || key[i - 1] == b'/';
+            match key[i] {
+                b':' if segment_start => {
+                    let end = match key[i..].iter().position(|c| *c == b'/') {
+                        None => key.len(),
+                        Some(pos) => i + pos,
+                    };
+                    let name = String::from_utf8_lossy(&key[i + 1..end]).to_string();
+                    assert!(
+                        !name.is_empty(),
+                        "missing parameter name in route {}",
+                        String::from_utf8_lossy(key)
+                    );
+                    tokens.push(Token::Param(name));
+                    i = end;
+                }
+                b'*' if segment_start => {
+                    let name = String::from_utf8_lossy(&key[i + 1..]).to_string();
+                    assert!(
+                        !name.is_empty() && !name.contains('/'),
+                        "invalid wildcard in route {}, it must be the last segment and have a name",
+                        String::from_utf8_lossy(key)
+                    );
+                    tokens.push(Token::Wildcard(name));
+                    i = key.len();
+                }
+                byte => {
+                    tokens.push(Token::Byte(byte));
+                    i += 1;
+                }
+            }
+        }
+        tokens
     }
 }
 
 #[derive(Debug)]
 struct Node<T> {
     children: Vec<Option<Node<T>>>,
+    param: Option<(String, Box<Node<T>>)>,
+    wildcard: Option<(String, T)>,
     value: Option<T>,
+    mode: MatchMode,
 }
 
 impl<T> Node<T> {
@@ -69,82 +146,159 @@ impl<T> Node<T> {
         children
     }
 
-    fn add_value_to_children(children: &mut [Option<Node<T>>], key: &[u8], value: T) {
-        let next = key[0] as usize;
-        if children[next].is_none() {
-            let new_node = Node::<T>::new();
-            children[next] = Some(new_node);
-        };
-        let mut child = children[next].take().unwrap();
-        child.add_value(&key[1..], value);
-        children[next] = Some(child);
-    }
-
     fn new() -> Self {
         let children = Self::empty_children();
         Node {
             children,
+            param: None,
+            wildcard: None,
             value: None,
+            mode: MatchMode::default(),
         }
     }
 
-    fn add_value(&mut self, key: &[u8], value: T) {
-        if key.is_empty() {
-            self.value = Some(value);
-            return;
+    fn add_value(&mut self, tokens: &[Token], value: T, mode: MatchMode) {
+        let token = match tokens.first() {
+            None => {
+                self.value = Some(value);
+                self.mode = mode;
+                return;
+            }
+            Some(token) => token,
+        };
+        match token {
+            Token::Byte(byte) => {
+                let next = *byte as usize;
+                if self.children[next].is_none() {
+                    self.children[next] = Some(Node::new());
+                };
+                let child = self.children[next].as_mut().unwrap();
+                child.add_value(&tokens[1..], value, mode);
+            }
+            Token::Param(name) => {
+                let (current, child) = self
+                    .param
+                    .get_or_insert_with(|| (name.clone(), Box::new(Node::new())));
+                assert_eq!(
+                    current, name,
+                    "conflicting parameter names :{} and :{}",
+                    current, name
+                );
+                child.add_value(&tokens[1..], value, mode);
+            }
+            Token::Wildcard(name) => {
+                if let Some((current, _)) = &self.wildcard {
+                    assert_eq!(
+                        current, name,
+                        "conflicting wildcard names *{} and *{}",
+                        current, name
+                    );
+                }
+                self.wildcard = Some((name.clone(), value));
+            }
         }
-        Self::add_value_to_children(&mut self.children, key, value);
     }
 
-    fn get_value(&self, key: &[u8]) -> Option<&T> {
-        if key.is_empty() {
-            return self.value.as_ref();
+    fn get_value(&self, tokens: &[Token]) -> Option<&T> {
+        let token = match tokens.first() {
+            None => return self.value.as_ref(),
+            Some(token) => token,
+        };
+        match token {
+            Token::Byte(byte) => {
+                let child = self.children[*byte as usize].as_ref()?;
+                child.get_value(&tokens[1..])
+            }
+            Token::Param(name) => match &self.param {
+                Some((current, child)) if current == name => child.get_value(&tokens[1..]),
+                _ => None,
+            },
+            Token::Wildcard(name) => match &self.wildcard {
+                Some((current, value)) if current == name => Some(value),
+                _ => None,
+            },
         }
-        let pos = key[0] as usize;
-        let children = &self.children;
-        let child = match &children[pos] {
-            None => return None,
-            Some(node) => node,
+    }
+
+    fn move_value_out(&mut self, tokens: &[Token]) -> Option<T> {
+        let token = match tokens.first() {
+            None => return self.value.take(),
+            Some(token) => token,
         };
-        child.get_value(&key[1..])
+        match token {
+            Token::Byte(byte) => {
+                let child = self.children[*byte as usize].as_mut()?;
+                child.move_value_out(&tokens[1..])
+            }
+            Token::Param(name) => match &mut self.param {
+                Some((current, child)) if current == name => child.move_value_out(&tokens[1..]),
+                _ => None,
+            },
+            Token::Wildcard(name) => match &self.wildcard {
+                Some((current, _)) if current == name => {
+                    self.wildcard.take().map(|(_, value)| value)
+                }
+                _ => None,
+            },
+        }
     }
 
-    fn get_value_prefix<'a>(&'a self, key: &[u8], prefix_value: Option<&'a T>) -> Option<&T> {
+    fn find<'a>(
+        &'a self,
+        key: &[u8],
+        segment_start: bool,
+        prefix: bool,
+        params: &mut Params,
+    ) -> Option<&'a T> {
         if key.is_empty() {
-            if self.value.is_none() {
-                return prefix_value;
+            if self.value.is_some() {
+                return self.value.as_ref();
             }
-            return self.value.as_ref();
+            return match &self.wildcard {
+                Some((name, value)) if segment_start => {
+                    params.insert(name.clone(), String::new());
+                    Some(value)
+                }
+                _ => None,
+            };
         }
-        let pos = key[0] as usize;
-        let children = &self.children;
-        let child = match &children[pos] {
-            None => {
-                if self.value.is_some() {
-                    return self.value.as_ref();
+        if let Some(child) = &self.children[key[0] as usize] {
+            if let Some(value) = child.find(&key[1..], key[0] == b'/', prefix, params) {
+                return Some(value);
+            }
+        }
+        if segment_start {
+            if let Some((name, child)) = &self.param {
+                let end = match key.iter().position(|c| *c == b'/') {
+                    None => key.len(),
+                    Some(pos) => pos,
+                };
+                if end > 0 {
+                    if let Some(value) = child.find(&key[end..], false, prefix, params) {
+                        let segment = String::from_utf8_lossy(&key[..end]).to_string();
+                        params.insert(name.clone(), segment);
+                        return Some(value);
+                    }
                 }
-                return prefix_value;
             }
-            Some(node) => node,
-        };
-        let next_parent = match &self.value {
-            None => prefix_value,
-            Some(value) => Some(value),
-        };
-        child.get_value_prefix(&key[1..], next_parent)
+            if let Some((name, value)) = &self.wildcard {
+                let rest = String::from_utf8_lossy(key).to_string();
+                params.insert(name.clone(), rest);
+                return Some(value);
+            }
+        }
+        if prefix && self.matches_prefix_of(key, segment_start) {
+            return self.value.as_ref();
+        }
+        None
     }
 
-    pub fn move_value_out(&mut self, key: &[u8]) -> Option<T> {
-        if key.is_empty() {
-            return self.value.take();
+    fn matches_prefix_of(&self, rest: &[u8], segment_start: bool) -> bool {
+        match self.mode {
+            MatchMode::Exact => false,
+            MatchMode::SegmentPrefix => segment_start || rest.first() == Some(&b'/'),
+            MatchMode::BytePrefix => true,
         }
-        let pos = key[0] as usize;
-        let children = &mut self.children;
-        let child = match &mut children[pos] {
-            None => return None,
-            Some(node) => node,
-        };
-        child.move_value_out(&key[1..])
     }
 }
 
@@ -156,8 +310,11 @@ mod tests {
     fn trie_adds_node() {
         let mut root = Node::<&str>::new();
         let index = "/a/b/c".as_bytes();
-        root.add_value(index, "a");
-        assert_eq!(Some(&"a"), root.get_value("/a/b/c".as_bytes()));
+        root.add_value(&Token::parse(index), "a", MatchMode::default());
+        assert_eq!(
+            Some(&"a"),
+            root.get_value(&Token::parse("/a/b/c".as_bytes()))
+        );
     }
 
     #[test]
@@ -168,7 +325,7 @@ mod tests {
             println!("action executed with param {}", param);
             String::from(param)
         };
-        root.add_value(key, Box::new(action));
+        root.add_value(key, Box::new(action), MatchMode::default());
         let action = root.get_value(key);
         let resp = action.unwrap()(String::from("value passed"));
         assert_eq!(resp, "value passed");
@@ -179,23 +336,23 @@ mod tests {
         let mut root = Trie::<String>::new();
         let mut key = "/a/b/c/d".as_bytes();
         let mut value = String::from("action for route /a/b/c/d");
-        root.add_value(key, value);
+        root.add_value(key, value, MatchMode::default());
 
         key = "/a/b".as_bytes();
         value = String::from("action for route /a/b");
-        root.add_value(key, value);
+        root.add_value(key, value, MatchMode::default());
 
         let value = root.get_value_prefix("/d".as_bytes());
         assert!(value.is_none());
 
         let value = root.get_value_prefix("/a/b/c".as_bytes());
-        assert_eq!(value.unwrap(), "action for route /a/b");
+        assert_eq!(value.unwrap().0, "action for route /a/b");
 
         let value = root.get_value_prefix("/a/b/c/d".as_bytes());
-        assert_eq!(value.unwrap(), "action for route /a/b/c/d");
+        assert_eq!(value.unwrap().0, "action for route /a/b/c/d");
 
         let value = root.get_value_prefix("/a/b/c/d/e".as_bytes());
-        assert_eq!(value.unwrap(), "action for route /a/b/c/d");
+        assert_eq!(value.unwrap().0, "action for route /a/b/c/d");
 
         let value = root.get_value_prefix("/".as_bytes());
         assert!(value.is_none());
@@ -206,8 +363,205 @@ mod tests {
         let mut root = Trie::<String>::new();
         let key = "/".as_bytes();
         let value = String::from("action for route /");
-        root.add_value(key, value);
+        root.add_value(key, value, MatchMode::default());
         let value = root.get_value_prefix("/example".as_bytes());
-        assert_eq!(value.unwrap(), "action for route /");
+        assert_eq!(value.unwrap().0, "action for route /");
+    }
+
+    #[test]
+    fn trie_parse_tokens() {
+        let tokens = Token::parse("/a:/:id/*rest".as_bytes());
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Byte(b'/'),
+                Token::Byte(b'a'),
+                Token::Byte(b':'),
+                Token::Byte(b'/'),
+                Token::Param("id".to_string()),
+                Token::Byte(b'/'),
+                Token::Wildcard("rest".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn trie_find_params() {
+        let mut root = Trie::<String>::new();
+        root.add_value(
+            "/users/:id/posts/:post".as_bytes(),
+            "post".to_string(),
+            MatchMode::default(),
+        );
+        root.add_value(
+            "/users/:id".as_bytes(),
+            "user".to_string(),
+            MatchMode::default(),
+        );
+        assert_eq!(
+            root.get_value("/users/:id".as_bytes()),
+            Some(&"user".to_string())
+        );
+
+        let (value, params) = root
+            .get_value_prefix("/users/1/posts/2".as_bytes())
+            .unwrap();
+        assert_eq!(value, "post");
+        assert_eq!(params.get("id").unwrap(), "1");
+        assert_eq!(params.get("post").unwrap(), "2");
+
+        let (value, params) = root.get_value_prefix("/users/1".as_bytes()).unwrap();
+        assert_eq!(value, "user");
+        assert_eq!(params.len(), 1);
+        assert_eq!(params.get("id").unwrap(), "1");
+
+        // An empty segment does not match a parameter.
+        assert!(root.get_value_prefix("/users/".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn trie_find_wildcard() {
+        let mut root = Trie::<String>::new();
+        root.add_value(
+            "/files/*path".as_bytes(),
+            "files".to_string(),
+            MatchMode::default(),
+        );
+
+        let (value, params) = root.get_value_prefix("/files/a/b.txt".as_bytes()).unwrap();
+        assert_eq!(value, "files");
+        assert_eq!(params.get("path").unwrap(), "a/b.txt");
+
+        let (value, params) = root.get_value_prefix("/files/".as_bytes()).unwrap();
+        assert_eq!(value, "files");
+        assert_eq!(params.get("path").unwrap(), "");
+
+        assert!(root.get_value_prefix("/other".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn trie_find_most_specific() {
+        let mut root = Trie::<String>::new();
+        root.add_value(
+            "/a/*rest".as_bytes(),
+            "wildcard".to_string(),
+            MatchMode::default(),
+        );
+        root.add_value(
+            "/a/:id".as_bytes(),
+            "param".to_string(),
+            MatchMode::default(),
+        );
+        root.add_value(
+            "/a/new".as_bytes(),
+            "static".to_string(),
+            MatchMode::default(),
+        );
+
+        let (value, params) = root.get_value_prefix("/a/new".as_bytes()).unwrap();
+        assert_eq!(value, "static");
+        assert!(params.is_empty());
+
+        let (value, params) = root.get_value_prefix("/a/news".as_bytes()).unwrap();
+        assert_eq!(value, "param");
+        assert_eq!(params.get("id").unwrap(), "news");
+
+        let (value, params) = root.get_value_prefix("/a/b/c".as_bytes()).unwrap();
+        assert_eq!(value, "wildcard");
+        assert_eq!(params.get("rest").unwrap(), "b/c");
+    }
+
+    #[test]
+    fn trie_move_value_out_params() {
+        let mut root = Trie::<String>::new();
+        root.add_value(
+            "/a/:id".as_bytes(),
+            "param".to_string(),
+            MatchMode::default(),
+        );
+        root.add_value(
+            "/a/*rest".as_bytes(),
+            "wildcard".to_string(),
+            MatchMode::default(),
+        );
+        assert!(root.move_value_out("/a/:other".as_bytes()).is_none());
+        assert_eq!(root.move_value_out("/a/:id".as_bytes()).unwrap(), "param");
+        assert_eq!(
+            root.move_value_out("/a/*rest".as_bytes()).unwrap(),
+            "wildcard"
+        );
+        assert!(root.get_value_prefix("/a/b".as_bytes()).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn trie_conflicting_param_names() {
+        let mut root = Trie::<String>::new();
+        root.add_value("/a/:id".as_bytes(), "id".to_string(), MatchMode::default());
+        root.add_value(
+            "/a/:name".as_bytes(),
+            "name".to_string(),
+            MatchMode::default(),
+        );
+    }
+
+    #[test]
+    fn trie_find_prefix_match_modes() {
+        let mut root = Trie::<String>::new();
+        root.add_value(
+            "/api".as_bytes(),
+            "api".to_string(),
+            MatchMode::SegmentPrefix,
+        );
+        root.add_value("/exact".as_bytes(), "exact".to_string(), MatchMode::Exact);
+        root.add_value(
+            "/bytes".as_bytes(),
+            "bytes".to_string(),
+            MatchMode::BytePrefix,
+        );
+
+        assert_eq!(root.get_value_prefix("/api".as_bytes()).unwrap().0, "api");
+        assert_eq!(root.get_value_prefix("/api/a".as_bytes()).unwrap().0, "api");
+        assert!(root.get_value_prefix("/apixyz".as_bytes()).is_none());
+        assert!(root.get_value_prefix("/api-internal".as_bytes()).is_none());
+
+        assert_eq!(
+            root.get_value_prefix("/exact".as_bytes()).unwrap().0,
+            "exact"
+        );
+        assert!(root.get_value_prefix("/exact/a".as_bytes()).is_none());
+
+        assert_eq!(
+            root.get_value_prefix("/bytes/a".as_bytes()).unwrap().0,
+            "bytes"
+        );
+        assert_eq!(
+            root.get_value_prefix("/bytesxyz".as_bytes()).unwrap().0,
+            "bytes"
+        );
+    }
+
+    #[test]
+    fn trie_find_prefix_falls_back_to_parent_segment() {
+        let mut root = Trie::<String>::new();
+        root.add_value("/".as_bytes(), "root".to_string(), MatchMode::SegmentPrefix);
+        root.add_value(
+            "/api".as_bytes(),
+            "api".to_string(),
+            MatchMode::SegmentPrefix,
+        );
+        root.add_value(
+            "/users/:id".as_bytes(),
+            "user".to_string(),
+            MatchMode::SegmentPrefix,
+        );
+
+        assert_eq!(
+            root.get_value_prefix("/apixyz".as_bytes()).unwrap().0,
+            "root"
+        );
+        let (value, params) = root.get_value_prefix("/users/1/a".as_bytes()).unwrap();
+        assert_eq!(value, "user");
+        assert_eq!(params.get("id").unwrap(), "1");
     }
 }
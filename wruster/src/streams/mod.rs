@@ -10,6 +10,7 @@ use polling::Source;
 
 pub mod cancellable_stream;
 pub mod observable;
+pub mod shared_reader;
 pub mod timeout_stream;
 pub mod tls;
 
@@ -0,0 +1,101 @@
+/*!
+Contains a buffered reader that can be lent, by value, to the consumers of a
+stream and that is given back when the consumer drops it, so the bytes read
+ahead into its buffer are not lost between consumers.
+*/
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Read};
+use std::rc::Rc;
+
+type Slot<R> = Rc<RefCell<Option<BufReader<R>>>>;
+
+/**
+Owns a [`BufReader`] that is lent to the consumers of the underlying reader
+through the [`SharedReader::lend`] method. The server uses it to keep the
+bytes of pipelined requests read ahead while reading a previous request in the
+same connection.
+*/
+pub struct SharedReader<R: Read> {
+    slot: Slot<R>,
+}
+
+impl<R: Read> SharedReader<R> {
+    pub fn new(from: R) -> Self {
+        let reader = BufReader::new(from);
+        SharedReader {
+            slot: Rc::new(RefCell::new(Some(reader))),
+        }
+    }
+
+    /// Lends the reader, it returns None if the reader is already lent.
+    pub fn lend(&self) -> Option<LentReader<R>> {
+        let reader = self.slot.borrow_mut().take()?;
+        Some(LentReader {
+            reader: Some(reader),
+            slot: Rc::clone(&self.slot),
+        })
+    }
+}
+
+/// A reader lent by a [`SharedReader`], it's given back when dropped.
+pub struct LentReader<R: Read> {
+    reader: Option<BufReader<R>>,
+    slot: Slot<R>,
+}
+
+impl<R: Read> LentReader<R> {
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader().get_mut()
+    }
+
+    fn reader(&mut self) -> &mut BufReader<R> {
+        // The reader is only taken when the LentReader is dropped.
+        self.reader.as_mut().unwrap()
+    }
+}
+
+impl<R: Read> Read for LentReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader().read(buf)
+    }
+}
+
+impl<R: Read> BufRead for LentReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader().fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader().consume(amt)
+    }
+}
+
+impl<R: Read> Drop for LentReader<R> {
+    fn drop(&mut self) {
+        *self.slot.borrow_mut() = self.reader.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn lent_reader_keeps_buffered_bytes() {
+        let shared = SharedReader::new(Cursor::new("first\nsecond\n"));
+        let mut reader = shared.lend().unwrap();
+        assert!(shared.lend().is_none());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "first\n");
+        drop(reader);
+
+        let mut reader = shared.lend().unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "second\n");
+    }
+}
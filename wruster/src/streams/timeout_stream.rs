@@ -89,6 +89,13 @@ where
             write: write_timeout,
         }
     }
+
+    /// Restarts the time counted for the read and write operations, so the
+    /// stream can be used to read a new message with the same timeouts.
+    pub fn reset(&mut self) {
+        self.ongoing_read = None;
+        self.ongoing_write = None;
+    }
 }
 
 impl<T> Read for TimeoutStream<T>
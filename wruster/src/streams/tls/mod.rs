@@ -1,5 +1,4 @@
 use std::{
-    fmt::{Debug, Display},
     io::{self, BufReader, Read, Write},
     net::{Shutdown, TcpStream},
     path::PathBuf,
@@ -101,28 +100,6 @@ impl Write for Stream {
     }
 }
 
-struct ComposeError {
-    a: io::Error,
-    b: io::Error,
-}
-
-impl Display for ComposeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Multiple Errors: {} {}", &self.a, &self.b)
-    }
-}
-
-impl Debug for ComposeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ComposeError")
-            .field("a", &self.a)
-            .field("b", &self.b)
-            .finish()
-    }
-}
-
-impl std::error::Error for ComposeError {}
-
 /**
 Represents a Certificate that can be used in the TLS connections.
 */
@@ -46,7 +46,7 @@ impl DynamicWorker {
                     },
                 }
                 finished();
-                debug!("worker {} stopped", id.to_string());
+                debug!("worker {} stopped", id);
                 break;
             }
         });
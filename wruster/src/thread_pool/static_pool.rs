@@ -24,7 +24,7 @@ impl Worker {
                 debug!("action executed");
                 continue;
             }
-            debug!("woker: {} stopped", id.to_string());
+            debug!("woker: {} stopped", id);
             break;
         });
         // Wait for the thread to be initialized.
@@ -82,7 +82,7 @@ impl Static {
             Ok(_) => {
                 debug!(
                     "run: current worker: {} not busy",
-                    self.workers[self.next].id.to_string()
+                    self.workers[self.next].id
                 );
                 self.next = (self.next + 1) % self.size;
                 return Ok(());
@@ -103,7 +103,7 @@ impl Static {
         self.next = from;
         debug!(
             "run: found the worker: {}, that is not busy",
-            self.workers[self.next].id.to_string()
+            self.workers[self.next].id
         );
         self.next = (self.next + 1) % self.size;
         Ok(())
@@ -1,6 +1,15 @@
+// The existing integration tests predate these lints, so they are allowed in them.
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::redundant_field_names,
+    clippy::to_string_in_format_args,
+    clippy::unused_io_amount
+)]
+
 use std::error::Error;
 use std::io::{self, Read, Write};
 use std::net::{Shutdown, TcpStream};
+use std::str::FromStr;
 use std::thread;
 use std::time::{self, Duration};
 
@@ -86,8 +95,10 @@ test";
     client.connect().unwrap();
     client.send(request.as_bytes()).unwrap();
     let stream = client.stream().unwrap();
-    let response = Response::read_from(stream).unwrap();
+    let mut response = Response::read_from(stream).unwrap();
     assert_eq!(response.status, StatusCode::OK);
+    // The Date header changes in every response.
+    assert!(response.headers.remove("Date").is_some());
     let got_headers = response
         .headers
         .iter()
@@ -106,6 +117,54 @@ test";
     server.shutdown().unwrap()
 }
 
+#[test]
+fn server_handles_chunked_requests() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        let mut content: Vec<u8> = Vec::new();
+        request
+            .body
+            .as_mut()
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        let content = String::from_utf8_lossy(&content);
+        if &content == "test" {
+            Response::from_status(StatusCode::OK)
+        } else {
+            Response::from_status(StatusCode::InternalServerError)
+        }
+    });
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    routes.add("/", http::HttpMethod::POST, handler);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    let request = "POST / HTTP/1.1\r\n\
+Transfer-Encoding: chunked\r\n\
+\r\n\
+2\r\n\
+te\r\n\
+2;ext=1\r\n\
+st\r\n\
+0\r\n\
+Trailer-Field: value\r\n\
+\r\n";
+    client.connect().unwrap();
+    // Send two requests in the same connection to check the chunked body
+    // is completely consumed.
+    for _ in 0..2 {
+        client.send(request.as_bytes()).unwrap();
+        let stream = client.stream().unwrap();
+        let response = Response::read_from(stream).unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+    }
+    server.shutdown().unwrap()
+}
+
 #[test]
 fn server_tls_handles_requests() {
     let mut server = Server::new();
@@ -143,8 +202,10 @@ Content-Length: 4\r\n\
 test";
     client.write(request.as_bytes()).unwrap();
 
-    let response = Response::read_from(client.stream).unwrap();
+    let mut response = Response::read_from(client.stream).unwrap();
     assert_eq!(response.status, StatusCode::OK);
+    // The Date header changes in every response.
+    assert!(response.headers.remove("Date").is_some());
     let got_headers = response
         .headers
         .iter()
@@ -163,6 +224,519 @@ test";
     server.shutdown().unwrap()
 }
 
+#[test]
+fn server_answers_method_not_allowed_and_options() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |_| Response::from_status(StatusCode::OK));
+    routes.add("/a", http::HttpMethod::GET, handler);
+    let handler: HttpHandler = Box::new(move |_| Response::from_status(StatusCode::OK));
+    routes.add("/a", http::HttpMethod::POST, handler);
+    let handler: HttpHandler = Box::new(move |_| Response::from_status(StatusCode::NoContent));
+    routes.add("/b", http::HttpMethod::OPTIONS, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    client.send(b"DELETE /a HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::MethodNotAllowed);
+    let allow = response.headers.get("Allow").unwrap();
+    assert_eq!(allow, &vec!["GET, HEAD, POST, OPTIONS".to_string()]);
+
+    client.send(b"OPTIONS /a HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let allow = response.headers.get("Allow").unwrap();
+    assert_eq!(allow, &vec!["GET, HEAD, POST, OPTIONS".to_string()]);
+
+    // An explicit OPTIONS handler takes precedence.
+    client.send(b"OPTIONS /b HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::NoContent);
+
+    client.send(b"GET /c HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::NotFound);
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_handles_extension_methods() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        Response::from_str(&format!("{} {}", request.method, request.uri.path())).unwrap()
+    });
+    let mkcol = http::HttpMethod::from_str("MKCOL").unwrap();
+    routes.add("/dav", mkcol, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    client.send(b"MKCOL /dav/col HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let mut content = String::new();
+    response
+        .body
+        .unwrap()
+        .content
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "MKCOL /dav/col");
+
+    client.send(b"PROPFIND /dav HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::MethodNotAllowed);
+    assert_eq!(response.headers.get_first("Allow"), Some("OPTIONS, MKCOL"));
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_answers_head_requests_without_body() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |_| Response::from_str("hello").unwrap());
+    routes.add("/a", http::HttpMethod::GET, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    client.send(b"HEAD /a HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let length = response.headers.get("Content-Length").unwrap();
+    assert_eq!(length, &vec!["5".to_string()]);
+
+    // The next response in the connection must be the one for the GET
+    // request, so no bytes of the body were sent for the HEAD request.
+    client.send(b"GET /a HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let mut content = String::new();
+    response
+        .body
+        .unwrap()
+        .content
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "hello");
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_routes_decoded_paths() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        let name = request.uri.query_param("name").unwrap_or("none");
+        Response::from_str(&format!("{} {}", request.uri.path(), name)).unwrap()
+    });
+    routes.add("/a b", http::HttpMethod::GET, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    client
+        .send(b"GET /a%20b/c?name=wruster HTTP/1.1\r\n\r\n")
+        .unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let mut content = String::new();
+    response
+        .body
+        .unwrap()
+        .content
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "/a b/c wruster");
+
+    client
+        .send(b"GET /a%20b/%2e%2e/x HTTP/1.1\r\n\r\n")
+        .unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::BadRequest);
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_enforces_request_limits() {
+    let limits = http::Limits {
+        max_request_line_length: 64,
+        max_header_line_length: 64,
+        max_headers: 4,
+        max_headers_size: 256,
+        max_body_size: 8,
+    };
+    let mut server = Server::new().with_limits(limits);
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |_| Response::from_status(StatusCode::OK));
+    routes.add("/", http::HttpMethod::POST, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+    thread::sleep(time::Duration::from_secs(1));
+
+    let requests: Vec<(String, StatusCode)> = vec![
+        (
+            "POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\ntest".to_string(),
+            StatusCode::OK,
+        ),
+        (
+            format!("POST /{} HTTP/1.1\r\n\r\n", "a".repeat(64)),
+            StatusCode::RequestURITooLarge,
+        ),
+        (
+            format!("POST / HTTP/1.1\r\nA: {}\r\n\r\n", "a".repeat(64)),
+            StatusCode::RequestHeaderFieldsTooLarge,
+        ),
+        (
+            "POST / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\nD: 4\r\nE: 5\r\n\r\n".to_string(),
+            StatusCode::RequestHeaderFieldsTooLarge,
+        ),
+        (
+            "POST / HTTP/1.1\r\nContent-Length: 9\r\n\r\n123456789".to_string(),
+            StatusCode::RequestEntityTooLarge,
+        ),
+    ];
+    for (request, status) in requests {
+        let mut client = TcpClient {
+            addr: addr.clone(),
+            stream: None,
+        };
+        client.connect().unwrap();
+        client.send(request.as_bytes()).unwrap();
+        let response = Response::read_from(client.stream().unwrap()).unwrap();
+        assert_eq!(response.status, status);
+    }
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_rejects_too_large_chunked_bodies() {
+    let limits = http::Limits {
+        max_header_line_length: 64,
+        max_body_size: 8,
+        ..http::Limits::default()
+    };
+    let mut server = Server::new().with_limits(limits);
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        let mut content = String::new();
+        match request.body.as_mut().unwrap().read_to_string(&mut content) {
+            Ok(_) => Response::from_status(StatusCode::OK),
+            Err(_) => Response::from_status(StatusCode::InternalServerError),
+        }
+    });
+    routes.add("/", http::HttpMethod::POST, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+    thread::sleep(time::Duration::from_secs(1));
+
+    let requests = vec![
+        "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n12345\r\n5\r\n67890\r\n0\r\n\r\n"
+            .to_string(),
+        format!(
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1;a={}\r\n1\r\n0\r\n\r\n",
+            "a".repeat(64)
+        ),
+    ];
+    for request in requests {
+        let mut client = TcpClient {
+            addr: addr.clone(),
+            stream: None,
+        };
+        client.connect().unwrap();
+        client.send(request.as_bytes()).unwrap();
+        let response = Response::read_from(client.stream().unwrap()).unwrap();
+        assert_eq!(response.status, StatusCode::RequestEntityTooLarge);
+        assert_eq!(response.headers.get_first("Connection"), Some("close"));
+        thread::sleep(time::Duration::from_millis(500));
+        assert!(client.is_closed());
+    }
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_answers_pipelined_requests_in_order() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        let mut content = String::new();
+        if let Some(body) = request.body.as_mut() {
+            body.content.read_to_string(&mut content).unwrap();
+        }
+        Response::from_str(&format!("{}:{}", request.uri.path(), content)).unwrap()
+    });
+    routes.add("/", http::HttpMethod::POST, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    // All the requests are sent in a single write, so the server reads
+    // ahead the bytes of the next requests while reading the first one.
+    let requests = "POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\none\
+POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\ntwo\r\n0\r\n\r\n\
+POST /c HTTP/1.1\r\nContent-Length: 5\r\nConnection: close\r\n\r\nthree";
+    client.send(requests.as_bytes()).unwrap();
+    let mut responses = String::new();
+    client
+        .stream()
+        .unwrap()
+        .read_to_string(&mut responses)
+        .unwrap();
+    let bodies = responses
+        .split("\r\n\r\n")
+        .skip(1)
+        .map(|part| part.split("HTTP/1.1").next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(bodies, vec!["/a:one", "/b:two", "/c:three"]);
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_answers_expect_continue() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |request| {
+        let mut content = String::new();
+        let body = request.body.as_mut().unwrap();
+        body.content.read_to_string(&mut content).unwrap();
+        Response::from_str(&content).unwrap()
+    });
+    routes.add("/echo", http::HttpMethod::POST, handler);
+    let handler: HttpHandler =
+        Box::new(move |_| Response::from_status(StatusCode::RequestEntityTooLarge));
+    routes.add("/reject", http::HttpMethod::POST, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+    thread::sleep(time::Duration::from_secs(1));
+
+    // The server sends 100 Continue when the handler reads the body.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    client
+        .send(b"POST /echo HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 4\r\n\r\n")
+        .unwrap();
+    let mut stream = client.stream().unwrap();
+    let mut interim = [0; 25];
+    stream.read_exact(&mut interim).unwrap();
+    assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+    client.send(b"test").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let mut content = String::new();
+    response
+        .body
+        .unwrap()
+        .content
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "test");
+
+    // The handler rejects the request without reading the body.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    client
+        .send(b"POST /reject HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 4\r\n\r\n")
+        .unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::RequestEntityTooLarge);
+    thread::sleep(time::Duration::from_millis(100));
+    assert!(client.is_closed());
+
+    // Expectations other than 100-continue are not supported.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    client
+        .send(b"POST /echo HTTP/1.1\r\nExpect: something\r\nContent-Length: 4\r\n\r\ntest")
+        .unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::ExpectationFailed);
+
+    // The expectations of HTTP/1.0 requests are ignored.
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+    client
+        .send(b"POST /echo HTTP/1.0\r\nExpect: something\r\nContent-Length: 4\r\n\r\ntest")
+        .unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    let mut content = String::new();
+    response
+        .body
+        .unwrap()
+        .content
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "test");
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_answers_with_the_request_version() {
+    let mut server = Server::new();
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |_| Response::from_str("hello").unwrap());
+    routes.add("/a", http::HttpMethod::GET, handler);
+    let handler: HttpHandler = Box::new(move |_| {
+        Response::from_chunked_content(io::Cursor::new("chunked"), mime::TEXT_PLAIN)
+    });
+    routes.add("/chunked", http::HttpMethod::GET, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+    thread::sleep(time::Duration::from_secs(1));
+
+    // A HTTP/1.0 connection is closed after the response by default.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    client.send(b"GET /a HTTP/1.0\r\n\r\n").unwrap();
+    let mut response = String::new();
+    client
+        .stream()
+        .unwrap()
+        .read_to_string(&mut response)
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(!response.contains("Connection"));
+    assert!(response.ends_with("hello"));
+
+    // The connection is kept open when the client asks for it.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    for _ in 0..2 {
+        client
+            .send(b"GET /a HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        let response = Response::read_from(client.stream().unwrap()).unwrap();
+        assert_eq!(response.version, http::Version::HTTP1_0);
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.headers.connection(), vec!["keep-alive"]);
+        response.body.unwrap().ensure_read().unwrap();
+    }
+
+    // A chunked body is sent as is to a HTTP/1.0 client, and the connection
+    // is closed to signal the end of the body.
+    let mut client = TcpClient {
+        addr: addr.clone(),
+        stream: None,
+    };
+    client.connect().unwrap();
+    client
+        .send(b"GET /chunked HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    client
+        .stream()
+        .unwrap()
+        .read_to_string(&mut response)
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(!response.contains("Transfer-Encoding"));
+    assert!(!response.contains("keep-alive"));
+    assert!(response.ends_with("\r\n\r\nchunked"));
+
+    // Unsupported versions are rejected.
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+    client.send(b"GET /a HTTP/2.0\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::HTTPVersionNotSupported);
+    server.shutdown().unwrap()
+}
+
+#[test]
+fn server_adds_date_and_default_headers() {
+    let mut headers = Headers::new();
+    headers.add(Header::new("Server", "test"));
+    headers.add(Header::new("Strict-Transport-Security", "max-age=60"));
+    let mut server = Server::new().with_default_headers(headers);
+    let routes = router::Router::new();
+    let handler: HttpHandler = Box::new(move |_| Response::from_str("hello").unwrap());
+    routes.add("/a", http::HttpMethod::GET, handler);
+    let handler: HttpHandler = Box::new(move |_| {
+        let mut response = Response::from_str("hello").unwrap();
+        response.headers.add(Header::new("Server", "handler"));
+        response
+    });
+    routes.add("/b", http::HttpMethod::GET, handler);
+    let port = get_free_port();
+    let addr = format!("127.0.0.1:{}", port);
+    server.run(&addr, routes).unwrap();
+
+    thread::sleep(time::Duration::from_secs(1));
+    let mut client = TcpClient { addr, stream: None };
+    client.connect().unwrap();
+
+    client.send(b"GET /a HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.headers.get_first("Server"), Some("test"));
+    assert_eq!(
+        response.headers.get_first("Strict-Transport-Security"),
+        Some("max-age=60")
+    );
+    assert!(response.headers.date().unwrap().ends_with(" GMT"));
+    response.body.unwrap().ensure_read().unwrap();
+
+    // The headers set by the handler are not replaced.
+    client.send(b"GET /b HTTP/1.1\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(
+        response.headers.get("Server"),
+        Some(&vec!["handler".to_string()])
+    );
+    assert!(response.headers.contains("Strict-Transport-Security"));
+    assert!(response.headers.contains("Date"));
+    response.body.unwrap().ensure_read().unwrap();
+
+    // Requests that can't be read are also answered with the headers.
+    client.send(b"GET /b HTTP/2.0\r\n\r\n").unwrap();
+    let response = Response::read_from(client.stream().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::HTTPVersionNotSupported);
+    assert_eq!(response.headers.get_first("Server"), Some("test"));
+    assert!(response.headers.contains("Date"));
+    server.shutdown().unwrap()
+}
+
 #[test]
 fn server_shutdowns() {
     let mut server = Server::new();
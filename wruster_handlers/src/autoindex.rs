@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::time::SystemTime;
+
+use mime_guess::mime;
+use wruster::http::headers::Header;
+use wruster::http::negotiation::negotiate;
+use wruster::http::{format_http_date, Request, Response, StatusCode};
+
+// An entry of a directory listing.
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortBy {
+    fn from_param(param: Option<&str>) -> SortBy {
+        match param {
+            Some("size") => SortBy::Size,
+            Some("modified") => SortBy::Modified,
+            _ => SortBy::Name,
+        }
+    }
+
+    fn as_param(&self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::Size => "size",
+            SortBy::Modified => "modified",
+        }
+    }
+}
+
+/**
+Creates a response with the listing of the entries of a directory, in HTML or
+in JSON depending on the ``Accept`` header of the request. The entries are
+sorted by the ``sort`` query parameter of the request: ``name``, ``size`` or
+``modified``, in the order given by the ``order`` parameter: ``asc`` or
+``desc``, always listing the directories first. Only the entries accepted by
+the ``visible`` function are listed.
+*/
+pub(crate) fn listing(dir: &Path, request: &Request, visible: &dyn Fn(&Path) -> bool) -> Response {
+    let supported = [mime::TEXT_HTML_UTF_8, mime::APPLICATION_JSON];
+    let mime_type = match negotiate(&request.headers, &supported) {
+        Ok(mime_type) => mime_type,
+        Err(status) => return Response::from_status(status),
+    };
+    let mut entries = match read_entries(dir, visible) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("error listing directory {:?}, error info: {}", dir, err);
+            if let io::ErrorKind::PermissionDenied = err.kind() {
+                return Response::from_status(StatusCode::Forbidden);
+            }
+            return Response::from_status(StatusCode::InternalServerError);
+        }
+    };
+    let sort_by = SortBy::from_param(request.uri.query_param("sort"));
+    let descending = request.uri.query_param("order") == Some("desc");
+    sort_entries(&mut entries, sort_by, descending);
+    let path = request.uri.path();
+    let content = match mime_type == mime::APPLICATION_JSON {
+        true => render_json(path, &entries),
+        false => render_html(path, &entries, sort_by, descending),
+    };
+    let length = content.len() as u64;
+    let mut response = Response::from_content(Cursor::new(content), length, mime_type);
+    response.headers.add(Header::new("Vary", "Accept"));
+    response
+}
+
+fn read_entries(dir: &Path, visible: &dyn Fn(&Path) -> bool) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !visible(&entry.path()) {
+            continue;
+        }
+        // The metadata of the targets of the symlinks is used, so they are
+        // listed as the entries they point to.
+        let metadata = match fs::metadata(entry.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        entries.push(Entry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        });
+    }
+    Ok(entries)
+}
+
+fn sort_entries(entries: &mut [Entry], sort_by: SortBy, descending: bool) {
+    entries.sort_by(|a, b| {
+        let ordering = match sort_by {
+            SortBy::Name => Ordering::Equal,
+            SortBy::Size => a.size.cmp(&b.size),
+            SortBy::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+        let ordering = if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        b.is_dir.cmp(&a.is_dir).then(ordering)
+    });
+}
+
+fn render_html(path: &str, entries: &[Entry], sort_by: SortBy, descending: bool) -> String {
+    let title = format!("Index of {}", escape_html(path));
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>\n</head>\n<body>", title);
+    let _ = writeln!(html, "<h1>{}</h1>\n<table>\n<thead>\n<tr>", title);
+    let columns = [
+        (SortBy::Name, "Name"),
+        (SortBy::Size, "Size"),
+        (SortBy::Modified, "Last modified"),
+    ];
+    for (column, label) in columns {
+        // Clicking the column the listing is sorted by reverses the order.
+        let order = match column == sort_by && !descending {
+            true => "desc",
+            false => "asc",
+        };
+        let _ = writeln!(
+            html,
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            column.as_param(),
+            order,
+            label
+        );
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+    if path != "/" {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td>-</td><td>-</td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let size = match entry.is_dir {
+            true => String::from("-"),
+            false => entry.size.to_string(),
+        };
+        let modified = entry
+            .modified
+            .map(format_http_date)
+            .unwrap_or_else(|| String::from("-"));
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>",
+            escape_html(&encode_path_segment(&entry.name)),
+            suffix,
+            escape_html(&entry.name),
+            suffix,
+            size,
+            modified
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+fn render_json(path: &str, entries: &[Entry]) -> String {
+    let mut json = format!("{{\"path\":\"{}\",\"entries\":[", escape_json(path));
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let kind = if entry.is_dir { "directory" } else { "file" };
+        let _ = write!(
+            json,
+            "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{}",
+            escape_json(&entry.name),
+            kind,
+            entry.size
+        );
+        match entry.modified {
+            Some(modified) => {
+                let _ = write!(json, ",\"modified\":\"{}\"}}", format_http_date(modified));
+            }
+            None => json.push_str(",\"modified\":null}"),
+        }
+    }
+    json.push_str("]}");
+    json
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Percent-encodes all the bytes of a path segment except the unreserved ones:
+// https://datatracker.ietf.org/doc/html/rfc3986#section-2.3.
+pub(crate) fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            byte => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
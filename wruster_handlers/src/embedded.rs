@@ -0,0 +1,174 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+use mime_guess::mime::{self, Mime};
+use wruster::http::{Request, Response, StatusCode};
+
+use super::{content_response, DEFAULT_INDEX_FILE};
+
+/**
+Represents a file whose content is embedded in the binary, see
+[`serve_embedded`].
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedFile {
+    /// The path of the file, relative to the root of the served tree and
+    /// without a leading slash, like ``css/app.css``.
+    pub path: &'static str,
+    /// The content of the file.
+    pub content: &'static [u8],
+    /// The media type of the file, like ``text/css``.
+    pub mime: &'static str,
+    /// The strong entity-tag of the content of the file, including the
+    /// double quotes, like ``"3f2a"``.
+    pub etag: &'static str,
+}
+
+/**
+Implements a handler that serves the files of a table embedded in the binary,
+usually generated at build time with [`write_embedded_files`]. The requests
+for a path ending with a slash are answered with the [`DEFAULT_INDEX_FILE`]
+under that path, if any. As the [`crate::serve_static`] handler, it answers
+the conditional and the ``Range`` requests using the entity-tags of the
+files, and, when it's also used for the ``HEAD`` requests, the server only
+sends the headers of the responses.
+
+# Examples
+
+```
+use wruster::http::{Request, StatusCode};
+use wruster_handlers::{serve_embedded, EmbeddedFile};
+
+static FILES: &[EmbeddedFile] = &[EmbeddedFile {
+    path: "index.html",
+    content: b"<h1>hello</h1>",
+    mime: "text/html",
+    etag: "\"1\"",
+}];
+
+let request = Request::read_from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+let response = serve_embedded(FILES, &request);
+assert_eq!(response.status, StatusCode::OK);
+assert_eq!(response.headers.get_first("ETag"), Some("\"1\""));
+```
+*/
+pub fn serve_embedded(files: &'static [EmbeddedFile], request: &Request) -> Response {
+    let mut path = request.uri.path().trim_start_matches('/').to_string();
+    if path.is_empty() || path.ends_with('/') {
+        path.push_str(DEFAULT_INDEX_FILE);
+    }
+    let file = match files.iter().find(|file| file.path == path) {
+        None => return Response::from_status(StatusCode::NotFound),
+        Some(file) => file,
+    };
+    let mime_type = file
+        .mime
+        .parse::<Mime>()
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let length = file.content.len() as u64;
+    let content = Cursor::new(file.content);
+    content_response(request, content, length, mime_type, file.etag, None)
+}
+
+/**
+Writes to the file ``to`` the table of [`EmbeddedFile`] of all the files
+under the directory ``dir``, as a Rust expression of type
+``&'static [EmbeddedFile]``, that includes the content of the files using the
+[`include_bytes`] macro. It's intended to be called from a build script,
+so it also tells cargo to run the build script again when any of the files
+changes. The hidden files, the ones with a name starting with a dot, are
+skipped.
+
+# Examples
+
+In the ``build.rs`` of a crate with ``wruster_handlers`` as a build
+dependency:
+
+```no_run
+use std::env;
+use std::path::Path;
+
+fn main() {
+    let to = Path::new(&env::var("OUT_DIR").unwrap()).join("assets.rs");
+    wruster_handlers::write_embedded_files(Path::new("assets"), &to).unwrap();
+}
+```
+
+Then, in the crate:
+
+```ignore
+use wruster_handlers::{serve_embedded, EmbeddedFile};
+
+static ASSETS: &[EmbeddedFile] = include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+let handler: HttpHandler = Box::new(move |request| serve_embedded(ASSETS, request));
+```
+
+# Errors
+
+Returns an error if there is any problem reading the directory tree or
+writing the ``to`` file.
+*/
+pub fn write_embedded_files(dir: &Path, to: &Path) -> io::Result<()> {
+    let dir = dir.canonicalize()?;
+    let mut files = Vec::new();
+    collect_files(&dir, &dir, &mut files)?;
+    files.sort();
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let mut table = String::from("&[\n");
+    for (path, file) in files {
+        println!("cargo:rerun-if-changed={}", file.display());
+        let content = fs::read(&file)?;
+        let mime_type = mime_guess::from_path(&file).first_or_octet_stream();
+        let etag = format!("\"{:x}-{:x}\"", content.len(), fnv1a(&content));
+        let file = file.to_str().ok_or_else(|| {
+            let msg = format!("invalid unicode in path {:?}", file);
+            io::Error::new(io::ErrorKind::InvalidData, msg)
+        })?;
+        let _ = writeln!(
+            table,
+            "    wruster_handlers::EmbeddedFile {{ path: {:?}, content: include_bytes!({:?}), mime: {:?}, etag: {:?} }},",
+            path,
+            file,
+            mime_type.essence_str(),
+            etag
+        );
+    }
+    table.push_str("]\n");
+    fs::write(to, table)
+}
+
+// Adds to files the path, relative to the root and using slashes as
+// separators, and the absolute path of each file under dir.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let metadata = fs::metadata(&path)?;
+        if metadata.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        // The path of the entry always starts with the root.
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let relative: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        files.push((relative.join("/"), path));
+    }
+    Ok(())
+}
+
+// Returns the 64 bits FNV-1a hash of a content, used to build the
+// entity-tags of the embedded files.
+fn fnv1a(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -3,18 +3,120 @@ Contains a set of helpful handlers, middlewares and utilities to create
 new handlers in a wruster web server.
 */
 use std::fs;
-use std::io::Read;
-use std::{io, path::PathBuf};
+use std::io;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[macro_use]
 extern crate log;
 
+mod autoindex;
+mod embedded;
+#[cfg(test)]
+mod tests;
+pub use self::embedded::{serve_embedded, write_embedded_files, EmbeddedFile};
+
+use mime_guess::mime::{self, Mime};
+use wruster::http::conditional::evaluate_preconditions;
 use wruster::http::headers::{Header, Headers};
-use wruster::http::{Body, Request, Response, StatusCode};
+use wruster::http::negotiation::{negotiate, negotiate_encoding};
+use wruster::http::range::{if_range_matches, parse_range, partial_content, range_not_satisfiable};
+use wruster::http::{format_http_date, Body, HttpMethod, Request, Response, StatusCode, Version};
 use wruster::router::HttpHandler;
 
+/// Defines the name of the default index file of the directories.
+pub const DEFAULT_INDEX_FILE: &str = "index.html";
+
+// The content codings of the precompressed versions of the files, in order of
+// preference, together with the extension of their names.
+const PRECOMPRESSED_EXTENSIONS: [(&str, &str); 2] = [("br", ".br"), ("gzip", ".gz")];
+
+/**
+Defines how the [`serve_static_with_options`] handler treats the symbolic
+links in the paths of the requests, and in the listings of the directories.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// The symbolic links are never followed, the requests for a path that
+    /// contains one are answered with a ``403 Forbidden``.
+    Deny,
+    /// The symbolic links are followed only if the path they resolve to is
+    /// inside the served directory, otherwise the requests are answered with
+    /// a ``403 Forbidden``.
+    FollowWithinRoot,
+    /// The symbolic links are always followed, even if they point outside
+    /// the served directory.
+    Follow,
+}
+
+/**
+Defines the options of the [`serve_static_with_options`] handler.
+
+# Examples
+
+```
+use wruster_handlers::StaticOptions;
+
+let options = StaticOptions {
+    autoindex: true,
+    ..StaticOptions::default()
+};
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticOptions {
+    /// The name of the file served when the path of a request is a
+    /// directory, by default [`DEFAULT_INDEX_FILE`]. If it's ``None`` or the
+    /// directory doesn't contain the file, the request is answered with the
+    /// listing of the directory, if enabled, or with a ``404 Not Found``.
+    pub index_file: Option<String>,
+    /// Enables answering the requests for the directories without an index
+    /// file with a listing of their contents, in HTML or, if the ``Accept``
+    /// header of the request prefers it, in JSON. The listing can be sorted
+    /// using the ``sort`` query parameter: ``name``, ``size`` or
+    /// ``modified``, and the ``order`` one: ``asc`` or ``desc``.
+    pub autoindex: bool,
+    /// Defines how the symbolic links are treated, by default
+    /// [`SymlinkPolicy::FollowWithinRoot`].
+    pub symlinks: SymlinkPolicy,
+    /// Allows serving the hidden files and directories, the ones with a name
+    /// starting with a dot, like ``.git`` or ``.env``, also when a symbolic
+    /// link points to them. By default, they are answered with a ``404 Not
+    /// Found`` and not included in the listings of the directories.
+    pub hidden_files: bool,
+    /// Enables serving the precompressed versions of the files, that are the
+    /// ones in the same directory with the same name plus the ``.br`` or
+    /// ``.gz`` extensions, when the ``Accept-Encoding`` header of the request
+    /// accepts their coding. Enabled by default.
+    pub precompressed: bool,
+    /// The path, relative to the served directory, of the file served for
+    /// the requests of a path that doesn't exist, or of a directory without
+    /// an index file nor a listing, like the ``index.html`` of a single page
+    /// application that handles the routing in the client. The file is only
+    /// served for the ``GET`` and ``HEAD`` requests that accept ``text/html``
+    /// and whose path doesn't have an extension, so the requests for missing
+    /// assets are still answered with a ``404 Not Found``. By default, there
+    /// is no fallback file.
+    pub fallback_file: Option<String>,
+}
+
+impl Default for StaticOptions {
+    fn default() -> Self {
+        StaticOptions {
+            index_file: Some(String::from(DEFAULT_INDEX_FILE)),
+            autoindex: false,
+            symlinks: SymlinkPolicy::FollowWithinRoot,
+            hidden_files: false,
+            precompressed: true,
+            fallback_file: None,
+        }
+    }
+}
+
 /**
-Implements a handler that serves the files in a directory tree.
+Implements a handler that serves the files in a directory tree, using the
+default [`StaticOptions`], see [`serve_static_with_options`].
 
 # Examples
 
@@ -36,55 +138,346 @@ server.wait().unwrap();
 ```
 */
 pub fn serve_static(dir: &str, request: &Request) -> Response {
+    serve_static_with_options(dir, &StaticOptions::default(), request)
+}
+
+/**
+Implements a handler that serves the files in a directory tree. The responses
+include the ``ETag`` and ``Last-Modified`` validators of the files, and the
+handler answers the conditional requests using them, replying with ``304 Not
+Modified`` or ``412 Precondition Failed`` when it corresponds. It also
+supports the ``Range`` requests, answering them with the requested ranges of
+the file.
+
+The requests for a directory without a trailing slash are redirected to the
+path with the slash, and the ones with it are answered with the index file of
+the directory or with its listing, as defined in the [`StaticOptions`]. The
+paths are resolved only inside the directory tree, according to the
+[`SymlinkPolicy`] of the options, and the hidden files are not served unless
+enabled in the options. If the client accepts it, a precompressed version of
+a file, like ``app.js.br`` or ``app.js.gz`` for ``app.js``, is served instead
+of the file, with the corresponding ``Content-Encoding`` header. Finally, the
+requests for the pages that don't exist can be answered with a fallback file,
+as the single page applications require.
+
+# Examples
+
+```no_run
+use wruster::router;
+use wruster::http;
+use wruster::Server;
+use wruster_handlers::{serve_static_with_options, StaticOptions};
+
+let addr = "localhost:8085";
+let dir = "./";
+let options = StaticOptions {
+    autoindex: true,
+    ..StaticOptions::default()
+};
+let routes = router::Router::new();
+let serve_dir: router::HttpHandler =
+    Box::new(move |request| serve_static_with_options(&dir, &options, &request));
+routes.add("/", http::HttpMethod::GET, serve_dir);
+let mut server = Server::new();
+server.run(addr, routes).unwrap();
+server.wait().unwrap();
+```
+*/
+pub fn serve_static_with_options(
+    dir: &str,
+    options: &StaticOptions,
+    request: &Request,
+) -> Response {
     let base_path: PathBuf = PathBuf::from(dir).canonicalize().unwrap();
-    let mut uri = request.uri.as_str();
-    if uri.starts_with('/') {
-        if uri.len() < 2 {
-            return Response::from_status(StatusCode::NotFound);
+    let mut path = base_path.clone();
+    path.push(request.uri.path().trim_start_matches('/'));
+    let metadata = check_path(&base_path, &path, options)
+        .and_then(|_| fs::metadata(&path).map_err(error_status));
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(status) => {
+            if status == StatusCode::NotFound {
+                if let Some(response) = serve_fallback(&base_path, options, request) {
+                    return response;
+                }
+            }
+            return Response::from_status(status);
         }
-        uri = &uri[1..]
+    };
+    if metadata.is_dir() {
+        return serve_dir(&base_path, &path, options, request);
     }
-    let mut path = base_path;
-    path.push(uri);
+    serve_file(&base_path, &path, &metadata, options, request)
+}
 
-    let metadata = match fs::metadata(&path) {
-        Ok(metadata) => metadata,
-        Err(err) => {
-            if let io::ErrorKind::NotFound = err.kind() {
-                return Response::from_status(StatusCode::NotFound);
+fn serve_dir(base: &Path, path: &Path, options: &StaticOptions, request: &Request) -> Response {
+    if !request.uri.path().ends_with('/') {
+        // The relative references in the index of the directory must be
+        // resolved from inside the directory. The location is built from the
+        // normalized path, so it's always an absolute path in this server,
+        // e.g.: a request for "//example.com" is not redirected to another
+        // host.
+        let mut location = String::from("/");
+        for segment in request.uri.path().split('/').filter(|s| !s.is_empty()) {
+            location.push_str(&autoindex::encode_path_segment(segment));
+            location.push('/');
+        }
+        if let Some(query) = request.uri.query() {
+            location.push('?');
+            location.push_str(query);
+        }
+        let mut response = Response::from_status(StatusCode::MovedPermanently);
+        response.headers.add(Header::new("Location", &location));
+        return response;
+    }
+    if let Some(index_file) = options.index_file.as_ref() {
+        let index_path = path.join(index_file);
+        let metadata = check_path(base, &index_path, options)
+            .ok()
+            .and_then(|_| fs::metadata(&index_path).ok());
+        if let Some(metadata) = metadata.filter(|metadata| metadata.is_file()) {
+            return serve_file(base, &index_path, &metadata, options, request);
+        }
+    }
+    if options.autoindex {
+        let visible = |entry: &Path| check_path(base, entry, options).is_ok();
+        return autoindex::listing(path, request, &visible);
+    }
+    serve_fallback(base, options, request)
+        .unwrap_or_else(|| Response::from_status(StatusCode::NotFound))
+}
+
+// Serves the fallback file, if it's defined in the options and the request is
+// for a page, not for an asset.
+fn serve_fallback(base: &Path, options: &StaticOptions, request: &Request) -> Option<Response> {
+    let fallback_file = options.fallback_file.as_ref()?;
+    let is_get = matches!(request.method, HttpMethod::GET | HttpMethod::HEAD);
+    let is_asset = Path::new(request.uri.path()).extension().is_some();
+    let accepts_html = negotiate(&request.headers, &[mime::TEXT_HTML]).is_ok();
+    if !is_get || is_asset || !accepts_html {
+        return None;
+    }
+    let path = base.join(fallback_file.trim_start_matches('/'));
+    check_path(base, &path, options).ok()?;
+    let metadata = fs::metadata(&path)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    let mut response = serve_file(base, &path, &metadata, options, request);
+    response.headers.add(Header::new("Vary", "Accept"));
+    Some(response)
+}
+
+fn serve_file(
+    base: &Path,
+    path: &Path,
+    metadata: &fs::Metadata,
+    options: &StaticOptions,
+    request: &Request,
+) -> Response {
+    // The media type is always the one of the original file, even if a
+    // precompressed version of it is served.
+    let mime_type = mime_guess::from_path(path).first_or_octet_stream();
+    let mut siblings = Vec::new();
+    if options.precompressed {
+        for (coding, extension) in PRECOMPRESSED_EXTENSIONS {
+            let mut sibling = path.as_os_str().to_owned();
+            sibling.push(extension);
+            let sibling = PathBuf::from(sibling);
+            let sibling_metadata = check_path(base, &sibling, options)
+                .ok()
+                .and_then(|_| fs::metadata(&sibling).ok());
+            if let Some(sibling_metadata) = sibling_metadata.filter(|m| m.is_file()) {
+                siblings.push((coding, sibling, sibling_metadata));
+            }
+        }
+    }
+    if siblings.is_empty() {
+        return open_file(path, metadata, mime_type, request);
+    }
+    let mut supported: Vec<&str> = siblings.iter().map(|(coding, _, _)| *coding).collect();
+    supported.push("identity");
+    let coding = negotiate_encoding(&request.headers, &supported).ok();
+    let sibling = siblings
+        .iter()
+        .find(|(sibling_coding, _, _)| Some(*sibling_coding) == coding.as_deref());
+    let mut response = match sibling {
+        None => open_file(path, metadata, mime_type, request),
+        Some((coding, sibling, sibling_metadata)) => {
+            let mut response = open_file(sibling, sibling_metadata, mime_type, request);
+            if response.body.is_some() {
+                response
+                    .headers
+                    .add(Header::new("Content-Encoding", coding));
             }
-            return Response::from_status(StatusCode::InternalServerError);
+            response
         }
     };
+    // The response depends on the Accept-Encoding header even when the
+    // original file is served.
+    response.headers.add(Header::new("Vary", "Accept-Encoding"));
+    response
+}
 
-    let content = match fs::File::open(&path) {
+fn open_file(path: &Path, metadata: &fs::Metadata, mime_type: Mime, request: &Request) -> Response {
+    let content = match fs::File::open(path) {
         Ok(content) => content,
-        Err(err) => {
-            if let io::ErrorKind::NotFound = err.kind() {
-                return Response::from_status(StatusCode::NotFound);
+        Err(err) => return Response::from_status(error_status(err)),
+    };
+    file_response(request, content, metadata, mime_type)
+}
+
+// Checks that a path inside the served directory can be served according to
+// the hidden files and the symlinks options.
+fn check_path(base: &Path, path: &Path, options: &StaticOptions) -> Result<(), StatusCode> {
+    let relative = path.strip_prefix(base).map_err(|_| StatusCode::Forbidden)?;
+    if !options.hidden_files && is_hidden(relative) {
+        return Err(StatusCode::NotFound);
+    }
+    match options.symlinks {
+        SymlinkPolicy::Deny => {
+            let mut current = base.to_path_buf();
+            for component in relative.components() {
+                current.push(component);
+                let metadata = fs::symlink_metadata(&current).map_err(error_status)?;
+                if metadata.file_type().is_symlink() {
+                    warn!("denied access to the symlink {:?}", current);
+                    return Err(StatusCode::Forbidden);
+                }
             }
-            return Response::from_status(StatusCode::InternalServerError);
+            Ok(())
+        }
+        SymlinkPolicy::Follow if options.hidden_files => Ok(()),
+        SymlinkPolicy::FollowWithinRoot | SymlinkPolicy::Follow => {
+            // The base path is already canonical.
+            let resolved = path.canonicalize().map_err(error_status)?;
+            let resolved = match resolved.strip_prefix(base) {
+                Ok(resolved) => resolved,
+                Err(_) if options.symlinks == SymlinkPolicy::Follow => return Ok(()),
+                Err(_) => {
+                    warn!("denied access to {:?}, it resolves outside the root", path);
+                    return Err(StatusCode::Forbidden);
+                }
+            };
+            // A symlink with a visible name can point to a hidden file.
+            if !options.hidden_files && is_hidden(resolved) {
+                return Err(StatusCode::NotFound);
+            }
+            Ok(())
         }
-    };
-    let mime_type = mime_guess::from_path(path).first_or_octet_stream();
-    let mut headers = Headers::new();
-    let content: Box<dyn Read> = Box::new(content);
-    headers.add(Header {
-        name: String::from("Content-Length"),
-        value: metadata.len().to_string(),
-    });
-    headers.add(Header {
-        name: String::from("Content-Type"),
-        value: mime_type.to_string(),
-    });
-    let body = Body::new(Some(mime_type), metadata.len(), content);
-    Response {
-        status: StatusCode::OK,
-        headers,
-        body: Some(body),
     }
 }
 
+// Returns true if any of the components of a path is hidden.
+fn is_hidden(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+fn error_status(err: io::Error) -> StatusCode {
+    match err.kind() {
+        io::ErrorKind::NotFound => StatusCode::NotFound,
+        _ => StatusCode::InternalServerError,
+    }
+}
+
+// Creates the response for a request of a file.
+fn file_response(
+    request: &Request,
+    content: fs::File,
+    metadata: &fs::Metadata,
+    mime_type: Mime,
+) -> Response {
+    let etag = file_etag(metadata);
+    let last_modified = metadata.modified().ok();
+    content_response(
+        request,
+        content,
+        metadata.len(),
+        mime_type,
+        &etag,
+        last_modified,
+    )
+}
+
+// Creates the response for a request of a content, evaluating the conditional
+// and range headers of the request against the validators of the content.
+fn content_response<T: Read + Seek + 'static>(
+    request: &Request,
+    content: T,
+    length: u64,
+    mime_type: Mime,
+    etag: &str,
+    last_modified: Option<SystemTime>,
+) -> Response {
+    let mut validators = Headers::new();
+    validators.add(Header::new("ETag", etag));
+    if let Some(last_modified) = last_modified {
+        let last_modified = format_http_date(last_modified);
+        validators.add(Header::new("Last-Modified", &last_modified));
+    }
+    if let Some(status) = evaluate_preconditions(request, Some(etag), last_modified) {
+        let mut response = Response::from_status(status);
+        if response.status == StatusCode::NotModified {
+            response.headers.add_missing(&validators);
+        }
+        return response;
+    }
+    let ranges = match if_range_matches(&request.headers, Some(etag), last_modified) {
+        true => parse_range(&request.headers, length),
+        false => Ok(None),
+    };
+    let mut response = match ranges {
+        Ok(None) => {
+            let mut headers = Headers::new();
+            let content: Box<dyn Read> = Box::new(content);
+            headers.set_content_length(length);
+            headers.set_content_type(&mime_type);
+            let body = Body::new(Some(mime_type), length, content);
+            Response {
+                version: Version::HTTP1_1,
+                status: StatusCode::OK,
+                headers,
+                body: Some(body),
+            }
+        }
+        Ok(Some(ranges)) => partial_content(content, length, mime_type, &ranges),
+        Err(_) => range_not_satisfiable(length),
+    };
+    response.headers.add_missing(&validators);
+    response.headers.add(Header::new("Accept-Ranges", "bytes"));
+    response
+}
+
+// Returns a strong entity-tag derived from the inode, the size and the
+// modification time of a file.
+fn file_etag(metadata: &fs::Metadata) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!(
+        "\"{:x}-{:x}-{:x}\"",
+        inode(metadata),
+        metadata.len(),
+        modified
+    )
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_: &fs::Metadata) -> u64 {
+    0
+}
+
 /**
 A middleware that uses the log to print the request and response to
 the standard output with INFO level.
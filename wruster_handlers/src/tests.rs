@@ -0,0 +1,895 @@
+use std::env;
+use std::process;
+
+use super::*;
+
+// A directory under the temporary directory of the system that is removed
+// when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("wruster_handlers_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path.canonicalize().unwrap())
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+
+    fn write(&self, path: &str, content: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn mkdir(&self, path: &str) {
+        fs::create_dir_all(self.0.join(path)).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn serve(dir: &TempDir, options: &StaticOptions, request: &str) -> Response {
+    let request = Request::read_from_str(request).unwrap();
+    serve_static_with_options(dir.path(), options, &request)
+}
+
+fn read_body(response: Response) -> String {
+    let mut content = String::new();
+    response.body.unwrap().read_to_string(&mut content).unwrap();
+    content
+}
+
+fn autoindex_options() -> StaticOptions {
+    StaticOptions {
+        autoindex: true,
+        ..StaticOptions::default()
+    }
+}
+
+#[test]
+fn serve_static_answers_range_requests() {
+    let dir = TempDir::new("range");
+    dir.write("file.txt", "0123456789");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers.get_first("Accept-Ranges"), Some("bytes"));
+    assert_eq!(read_body(response), "0123456789");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\nRange: bytes=2-4\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::PartialContent);
+    assert_eq!(response.headers.get_first("Accept-Ranges"), Some("bytes"));
+    assert_eq!(
+        response.headers.get_first("Content-Range"),
+        Some("bytes 2-4/10")
+    );
+    assert_eq!(response.headers.content_length(), Some(3));
+    assert_eq!(read_body(response), "234");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\nRange: bytes=-3\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::PartialContent);
+    assert_eq!(read_body(response), "789");
+}
+
+#[test]
+fn serve_static_answers_unsatisfiable_range_requests() {
+    let dir = TempDir::new("range_not_satisfiable");
+    dir.write("file.txt", "0123456789");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\nRange: bytes=20-\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::RequestedRangeNotSatisfiable);
+    assert_eq!(
+        response.headers.get_first("Content-Range"),
+        Some("bytes */10")
+    );
+}
+
+#[test]
+fn serve_static_answers_multiple_ranges() {
+    let dir = TempDir::new("multiple_ranges");
+    dir.write("file.txt", "0123456789");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\nRange: bytes=0-1, 8-\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::PartialContent);
+    let content_type = response.headers.get_first("Content-Type").unwrap();
+    assert!(content_type.starts_with("multipart/byteranges; boundary="));
+    let boundary = content_type.split_once("boundary=").unwrap().1.to_string();
+    let length = response.headers.content_length();
+    let body = read_body(response);
+    assert_eq!(length, Some(body.len() as u64));
+    let expected = format!(
+        "--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n--{0}--\r\n",
+        boundary
+    );
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn serve_static_honors_if_range() {
+    let dir = TempDir::new("if_range");
+    dir.write("file.txt", "0123456789");
+    let modified = fs::metadata(dir.0.join("file.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    let last_modified = format_http_date(modified);
+
+    // The range is served when the date matches the modification time.
+    let request = format!(
+        "GET /file.txt HTTP/1.1\r\nRange: bytes=2-4\r\nIf-Range: {}\r\n\r\n",
+        last_modified
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::PartialContent);
+    assert_eq!(read_body(response), "234");
+
+    // Otherwise, the full file is served.
+    let request = "GET /file.txt HTTP/1.1\r\nRange: bytes=2-4\r\n\
+If-Range: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(read_body(response), "0123456789");
+
+    let request = "GET /file.txt HTTP/1.1\r\nRange: bytes=2-4\r\nIf-Range: \"abc\"\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(read_body(response), "0123456789");
+}
+
+#[test]
+fn serve_static_sends_validators() {
+    let dir = TempDir::new("validators");
+    dir.write("file.txt", "0123456789");
+    let modified = fs::metadata(dir.0.join("file.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::OK);
+    let etag = response.headers.get_first("ETag").unwrap();
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert_eq!(
+        response.headers.get_first("Last-Modified"),
+        Some(format_http_date(modified).as_str())
+    );
+
+    // The entity-tag only changes when the file changes.
+    let etag = etag.to_string();
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.headers.get_first("ETag"), Some(etag.as_str()));
+    dir.write("file.txt", "01234567890");
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    );
+    assert_ne!(response.headers.get_first("ETag"), Some(etag.as_str()));
+}
+
+#[test]
+fn serve_static_answers_not_modified() {
+    let dir = TempDir::new("not_modified");
+    dir.write("file.txt", "0123456789");
+    let modified = fs::metadata(dir.0.join("file.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    let last_modified = format_http_date(modified);
+
+    let request = format!(
+        "GET /file.txt HTTP/1.1\r\nIf-Modified-Since: {}\r\n\r\n",
+        last_modified
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::NotModified);
+    assert!(response.headers.get_first("ETag").is_some());
+    assert_eq!(
+        response.headers.get_first("Last-Modified"),
+        Some(last_modified.as_str())
+    );
+    assert!(response.body.is_none());
+
+    let request =
+        "GET /file.txt HTTP/1.1\r\nIf-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(read_body(response), "0123456789");
+
+    // If-None-Match takes precedence over If-Modified-Since.
+    let etag = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    )
+    .headers
+    .get_first("ETag")
+    .unwrap()
+    .to_string();
+    let request = format!("GET /file.txt HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n", etag);
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::NotModified);
+    let request = format!(
+        "GET /file.txt HTTP/1.1\r\nIf-None-Match: \"other\"\r\nIf-Modified-Since: {}\r\n\r\n",
+        last_modified
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[test]
+fn serve_static_answers_precondition_failed() {
+    let dir = TempDir::new("precondition_failed");
+    dir.write("file.txt", "0123456789");
+    let modified = fs::metadata(dir.0.join("file.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    let etag = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\n\r\n",
+    )
+    .headers
+    .get_first("ETag")
+    .unwrap()
+    .to_string();
+
+    let request = "GET /file.txt HTTP/1.1\r\nIf-Match: \"other\"\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::PreconditionFailed);
+    let request = format!("GET /file.txt HTTP/1.1\r\nIf-Match: {}\r\n\r\n", etag);
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::OK);
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /file.txt HTTP/1.1\r\nIf-Match: *\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::OK);
+
+    let request =
+        "GET /file.txt HTTP/1.1\r\nIf-Unmodified-Since: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::PreconditionFailed);
+    let request = format!(
+        "GET /file.txt HTTP/1.1\r\nIf-Unmodified-Since: {}\r\n\r\n",
+        format_http_date(modified)
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[test]
+fn serve_static_redirects_directories_without_trailing_slash() {
+    let dir = TempDir::new("redirect");
+    dir.mkdir("docs/a b");
+    dir.mkdir("evil.com");
+    let options = StaticOptions::default();
+
+    let response = serve(&dir, &options, "GET /docs?sort=size HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::MovedPermanently);
+    assert_eq!(
+        response.headers.get_first("Location"),
+        Some("/docs/?sort=size")
+    );
+
+    let response = serve(&dir, &options, "GET /docs/a%20b HTTP/1.1\r\n\r\n");
+    assert_eq!(response.headers.get_first("Location"), Some("/docs/a%20b/"));
+
+    // The location is never a network-path reference to another host.
+    let response = serve(&dir, &options, "GET //evil.com HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::MovedPermanently);
+    assert_eq!(response.headers.get_first("Location"), Some("/evil.com/"));
+}
+
+#[test]
+fn serve_static_serves_index_files() {
+    let dir = TempDir::new("index");
+    dir.write("docs/index.html", "<h1>docs</h1>");
+    dir.mkdir("empty");
+
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /docs/ HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.headers.get_first("Content-Type"),
+        Some("text/html")
+    );
+    assert_eq!(read_body(response), "<h1>docs</h1>");
+
+    // Without an index file nor autoindex the directory is not found.
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /empty/ HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.status, StatusCode::NotFound);
+
+    let options = StaticOptions {
+        index_file: None,
+        ..autoindex_options()
+    };
+    let response = serve(&dir, &options, "GET /docs/ HTTP/1.1\r\n\r\n");
+    assert!(read_body(response).contains("index.html"));
+}
+
+#[test]
+fn serve_static_lists_directories_in_html() {
+    let dir = TempDir::new("autoindex_html");
+    dir.write("docs/<b>&.txt", "a");
+    dir.mkdir("docs/sub");
+
+    let response = serve(&dir, &autoindex_options(), "GET /docs/ HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.headers.get_first("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(response.headers.get_first("Vary"), Some("Accept"));
+    let listing = read_body(response);
+    assert!(listing.contains("<title>Index of /docs/</title>"));
+    assert!(listing.contains("<a href=\"%3Cb%3E%26.txt\">&lt;b&gt;&amp;.txt</a>"));
+    assert!(listing.contains("<a href=\"sub/\">sub/</a>"));
+    assert!(listing.contains("<a href=\"../\">../</a>"));
+    assert!(!listing.contains("<b>&"));
+}
+
+#[test]
+fn serve_static_lists_directories_in_json() {
+    let dir = TempDir::new("autoindex_json");
+    dir.write("a.txt", "aaa");
+    dir.write("b.txt", "b");
+    dir.mkdir("z");
+
+    let request = "GET / HTTP/1.1\r\nAccept: application/json\r\n\r\n";
+    let response = serve(&dir, &autoindex_options(), request);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.headers.get_first("Content-Type"),
+        Some("application/json")
+    );
+    let listing = read_body(response);
+    assert!(listing.starts_with("{\"path\":\"/\",\"entries\":["));
+    assert!(listing.contains("{\"name\":\"a.txt\",\"type\":\"file\",\"size\":3,"));
+    assert!(listing.contains("{\"name\":\"z\",\"type\":\"directory\","));
+
+    // The listing can't be sent in a media type the client doesn't accept.
+    let request = "GET / HTTP/1.1\r\nAccept: image/png\r\n\r\n";
+    let response = serve(&dir, &autoindex_options(), request);
+    assert_eq!(response.status, StatusCode::NotAcceptable);
+}
+
+#[test]
+fn serve_static_sorts_directory_listings() {
+    let dir = TempDir::new("autoindex_sort");
+    dir.write("a.txt", "aaa");
+    dir.write("b.txt", "b");
+    dir.write("c.txt", "cc");
+    dir.mkdir("z");
+
+    let cases = [
+        ("", ["z", "a.txt", "b.txt", "c.txt"]),
+        ("?order=desc", ["z", "c.txt", "b.txt", "a.txt"]),
+        ("?sort=size", ["z", "b.txt", "c.txt", "a.txt"]),
+        ("?sort=size&order=desc", ["z", "a.txt", "c.txt", "b.txt"]),
+        ("?sort=unknown", ["z", "a.txt", "b.txt", "c.txt"]),
+    ];
+    for (query, want) in cases {
+        let request = format!(
+            "GET /{} HTTP/1.1\r\nAccept: application/json\r\n\r\n",
+            query
+        );
+        let listing = read_body(serve(&dir, &autoindex_options(), &request));
+        let positions: Vec<usize> = want
+            .iter()
+            .map(|name| listing.find(&format!("\"name\":\"{}\"", name)).unwrap())
+            .collect();
+        let mut sorted = positions.clone();
+        sorted.sort_unstable();
+        assert_eq!(positions, sorted, "unexpected order for {:?}", query);
+    }
+}
+
+#[test]
+fn serve_static_hides_hidden_entries_in_listings() {
+    let dir = TempDir::new("autoindex_hidden");
+    dir.write("visible.txt", "a");
+    dir.write(".env", "secret");
+    dir.mkdir(".git");
+
+    let listing = read_body(serve(&dir, &autoindex_options(), "GET / HTTP/1.1\r\n\r\n"));
+    assert!(listing.contains("visible.txt"));
+    assert!(!listing.contains(".env"));
+    assert!(!listing.contains(".git"));
+
+    let options = StaticOptions {
+        hidden_files: true,
+        ..autoindex_options()
+    };
+    let listing = read_body(serve(&dir, &options, "GET / HTTP/1.1\r\n\r\n"));
+    assert!(listing.contains(".env"));
+    assert!(listing.contains(".git/"));
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_static_applies_symlink_policies() {
+    use std::os::unix::fs::symlink;
+
+    let dir = TempDir::new("symlinks");
+    let outside = TempDir::new("symlinks_outside");
+    dir.write("real/file.txt", "inside");
+    outside.write("file.txt", "outside");
+    symlink(dir.0.join("real/file.txt"), dir.0.join("link.txt")).unwrap();
+    symlink(dir.0.join("real"), dir.0.join("linkdir")).unwrap();
+    symlink(outside.0.join("file.txt"), dir.0.join("escape.txt")).unwrap();
+
+    let cases = [
+        (SymlinkPolicy::Deny, "/real/file.txt", StatusCode::OK),
+        (SymlinkPolicy::Deny, "/link.txt", StatusCode::Forbidden),
+        (
+            SymlinkPolicy::Deny,
+            "/linkdir/file.txt",
+            StatusCode::Forbidden,
+        ),
+        (SymlinkPolicy::Deny, "/escape.txt", StatusCode::Forbidden),
+        (SymlinkPolicy::FollowWithinRoot, "/link.txt", StatusCode::OK),
+        (
+            SymlinkPolicy::FollowWithinRoot,
+            "/linkdir/file.txt",
+            StatusCode::OK,
+        ),
+        (
+            SymlinkPolicy::FollowWithinRoot,
+            "/escape.txt",
+            StatusCode::Forbidden,
+        ),
+        (SymlinkPolicy::Follow, "/link.txt", StatusCode::OK),
+        (SymlinkPolicy::Follow, "/escape.txt", StatusCode::OK),
+    ];
+    for (symlinks, path, status) in cases {
+        let options = StaticOptions {
+            symlinks,
+            ..StaticOptions::default()
+        };
+        let request = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let response = serve(&dir, &options, &request);
+        assert_eq!(response.status, status, "{:?} {}", symlinks, path);
+    }
+
+    let options = StaticOptions {
+        symlinks: SymlinkPolicy::Follow,
+        ..StaticOptions::default()
+    };
+    let response = serve(&dir, &options, "GET /escape.txt HTTP/1.1\r\n\r\n");
+    assert_eq!(read_body(response), "outside");
+}
+
+#[test]
+fn serve_static_denies_hidden_files() {
+    let dir = TempDir::new("hidden");
+    dir.write(".env", "secret");
+    dir.write(".git/config", "secret");
+
+    for path in ["/.env", "/.git/config", "/.git/"] {
+        let request = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let response = serve(&dir, &autoindex_options(), &request);
+        assert_eq!(response.status, StatusCode::NotFound, "{}", path);
+    }
+
+    let options = StaticOptions {
+        hidden_files: true,
+        ..StaticOptions::default()
+    };
+    for path in ["/.env", "/.git/config"] {
+        let request = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let response = serve(&dir, &options, &request);
+        assert_eq!(response.status, StatusCode::OK, "{}", path);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_static_denies_symlinks_to_hidden_files() {
+    use std::os::unix::fs::symlink;
+
+    let dir = TempDir::new("hidden_symlinks");
+    dir.write(".env", "secret");
+    dir.write(".git/config", "secret");
+    symlink(dir.0.join(".env"), dir.0.join("env.txt")).unwrap();
+    symlink(dir.0.join(".git"), dir.0.join("git")).unwrap();
+
+    for symlinks in [SymlinkPolicy::FollowWithinRoot, SymlinkPolicy::Follow] {
+        let options = StaticOptions {
+            symlinks,
+            ..StaticOptions::default()
+        };
+        for path in ["/env.txt", "/git/config"] {
+            let request = format!("GET {} HTTP/1.1\r\n\r\n", path);
+            let response = serve(&dir, &options, &request);
+            assert_eq!(
+                response.status,
+                StatusCode::NotFound,
+                "{:?} {}",
+                symlinks,
+                path
+            );
+        }
+
+        let options = StaticOptions {
+            hidden_files: true,
+            ..options
+        };
+        let response = serve(&dir, &options, "GET /env.txt HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status, StatusCode::OK, "{:?}", symlinks);
+    }
+}
+
+fn precompressed_dir(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    dir.write("app.js", "identity");
+    dir.write("app.js.br", "brotli");
+    dir.write("app.js.gz", "gzip");
+    dir.write("style.css", "plain");
+    dir.write("style.css.gz", "gzip");
+    dir.write("plain.txt", "plain");
+    dir
+}
+
+#[test]
+fn serve_static_serves_precompressed_files() {
+    let dir = precompressed_dir("precompressed");
+    let cases = [
+        ("/app.js", "gzip, br", Some("br"), "brotli"),
+        ("/app.js", "br;q=0.5, gzip", Some("gzip"), "gzip"),
+        ("/app.js", "*", Some("br"), "brotli"),
+        ("/app.js", "deflate", None, "identity"),
+        ("/style.css", "br, gzip", Some("gzip"), "gzip"),
+        ("/style.css", "br", None, "plain"),
+    ];
+    for (path, accept_encoding, coding, content) in cases {
+        let request = format!(
+            "GET {} HTTP/1.1\r\nAccept-Encoding: {}\r\n\r\n",
+            path, accept_encoding
+        );
+        let response = serve(&dir, &StaticOptions::default(), &request);
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.headers.get_first("Content-Encoding"),
+            coding,
+            "{} {}",
+            path,
+            accept_encoding
+        );
+        assert_eq!(response.headers.get_first("Vary"), Some("Accept-Encoding"));
+        assert_eq!(
+            response.headers.content_length(),
+            Some(content.len() as u64)
+        );
+        // The media type is the one of the original file.
+        let mime_type = mime_guess::from_path(path).first_or_octet_stream();
+        assert_eq!(
+            response.headers.get_first("Content-Type"),
+            Some(mime_type.essence_str())
+        );
+        assert_eq!(read_body(response), content);
+    }
+
+    // Without the header, the original file is served.
+    let response = serve(
+        &dir,
+        &StaticOptions::default(),
+        "GET /app.js HTTP/1.1\r\n\r\n",
+    );
+    assert_eq!(response.headers.get_first("Content-Encoding"), None);
+    assert_eq!(read_body(response), "identity");
+}
+
+#[test]
+fn serve_static_varies_only_for_files_with_precompressed_versions() {
+    let dir = precompressed_dir("precompressed_vary");
+    let request = "GET /plain.txt HTTP/1.1\r\nAccept-Encoding: br, gzip\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.headers.get_first("Content-Encoding"), None);
+    assert_eq!(response.headers.get_first("Vary"), None);
+
+    let options = StaticOptions {
+        precompressed: false,
+        ..StaticOptions::default()
+    };
+    let request = "GET /app.js HTTP/1.1\r\nAccept-Encoding: br, gzip\r\n\r\n";
+    let response = serve(&dir, &options, request);
+    assert_eq!(response.headers.get_first("Content-Encoding"), None);
+    assert_eq!(response.headers.get_first("Vary"), None);
+    assert_eq!(read_body(response), "identity");
+}
+
+#[test]
+fn serve_static_answers_head_and_range_requests_for_precompressed_files() {
+    let dir = precompressed_dir("precompressed_head_range");
+
+    let request = "HEAD /app.js HTTP/1.1\r\nAccept-Encoding: br\r\n\r\n";
+    let head = serve(&dir, &StaticOptions::default(), request);
+    let request = "GET /app.js HTTP/1.1\r\nAccept-Encoding: br\r\n\r\n";
+    let get = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(head.status, StatusCode::OK);
+    for name in ["Content-Encoding", "Content-Length", "ETag", "Vary"] {
+        assert_eq!(
+            head.headers.get_first(name),
+            get.headers.get_first(name),
+            "{}",
+            name
+        );
+    }
+
+    // The ranges are ranges of the precompressed content.
+    let request = "GET /app.js HTTP/1.1\r\nAccept-Encoding: br\r\nRange: bytes=0-1\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::PartialContent);
+    assert_eq!(
+        response.headers.get_first("Content-Range"),
+        Some("bytes 0-1/6")
+    );
+    assert_eq!(response.headers.get_first("Content-Encoding"), Some("br"));
+    assert_eq!(response.headers.get_first("Vary"), Some("Accept-Encoding"));
+    assert_eq!(read_body(response), "br");
+
+    // The entity-tag of each version of the file is different.
+    let etag = get.headers.get_first("ETag").unwrap();
+    let request = format!(
+        "GET /app.js HTTP/1.1\r\nAccept-Encoding: br\r\nIf-None-Match: {}\r\n\r\n",
+        etag
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::NotModified);
+    assert_eq!(response.headers.get_first("Vary"), Some("Accept-Encoding"));
+    let request = format!(
+        "GET /app.js HTTP/1.1\r\nAccept-Encoding: gzip\r\nIf-None-Match: {}\r\n\r\n",
+        etag
+    );
+    let response = serve(&dir, &StaticOptions::default(), &request);
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[test]
+fn serve_static_serves_the_fallback_file() {
+    let dir = TempDir::new("fallback");
+    dir.write("index.html", "<h1>app</h1>");
+    dir.mkdir("empty");
+    let options = StaticOptions {
+        fallback_file: Some(String::from(DEFAULT_INDEX_FILE)),
+        ..StaticOptions::default()
+    };
+
+    let requests = [
+        "GET /users/1 HTTP/1.1\r\nAccept: text/html\r\n\r\n",
+        "HEAD /users/1 HTTP/1.1\r\nAccept: text/html,application/xhtml+xml,*/*;q=0.8\r\n\r\n",
+        "GET /users/1 HTTP/1.1\r\n\r\n",
+        // A directory without an index file nor a listing.
+        "GET /empty/ HTTP/1.1\r\nAccept: text/html\r\n\r\n",
+    ];
+    for request in requests {
+        let response = serve(&dir, &options, request);
+        assert_eq!(response.status, StatusCode::OK, "{}", request);
+        assert_eq!(
+            response.headers.get_first("Content-Type"),
+            Some("text/html")
+        );
+        assert_eq!(
+            response.headers.get("Vary"),
+            Some(&vec![String::from("Accept")])
+        );
+        assert_eq!(read_body(response), "<h1>app</h1>");
+    }
+}
+
+#[test]
+fn serve_static_only_serves_the_fallback_file_for_pages() {
+    let dir = TempDir::new("no_fallback");
+    dir.write("index.html", "<h1>app</h1>");
+    let options = StaticOptions {
+        fallback_file: Some(String::from(DEFAULT_INDEX_FILE)),
+        ..StaticOptions::default()
+    };
+
+    let requests = [
+        "GET /assets/app.js HTTP/1.1\r\nAccept: text/html\r\n\r\n",
+        "POST /users/1 HTTP/1.1\r\nAccept: text/html\r\n\r\n",
+        "GET /users/1 HTTP/1.1\r\nAccept: application/json\r\n\r\n",
+    ];
+    for request in requests {
+        let response = serve(&dir, &options, request);
+        assert_eq!(response.status, StatusCode::NotFound, "{}", request);
+    }
+
+    // Without a fallback file the pages are not found either.
+    let request = "GET /users/1 HTTP/1.1\r\nAccept: text/html\r\n\r\n";
+    let response = serve(&dir, &StaticOptions::default(), request);
+    assert_eq!(response.status, StatusCode::NotFound);
+}
+
+static EMBEDDED_FILES: &[EmbeddedFile] = &[
+    EmbeddedFile {
+        path: "index.html",
+        content: b"<h1>root</h1>",
+        mime: "text/html",
+        etag: "\"1\"",
+    },
+    EmbeddedFile {
+        path: "docs/index.html",
+        content: b"<h1>docs</h1>",
+        mime: "text/html",
+        etag: "\"2\"",
+    },
+    EmbeddedFile {
+        path: "app.js",
+        content: b"console.log(1)",
+        mime: "application/javascript",
+        etag: "\"3\"",
+    },
+];
+
+fn serve_embedded_str(request: &str) -> Response {
+    let request = Request::read_from_str(request).unwrap();
+    serve_embedded(EMBEDDED_FILES, &request)
+}
+
+#[test]
+fn serve_embedded_serves_files() {
+    let response = serve_embedded_str("GET /app.js HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.headers.get_first("Content-Type"),
+        Some("application/javascript")
+    );
+    assert_eq!(response.headers.content_length(), Some(14));
+    assert_eq!(response.headers.get_first("ETag"), Some("\"3\""));
+    assert_eq!(read_body(response), "console.log(1)");
+
+    for path in ["/missing.js", "/docs", "/docs/missing/"] {
+        let request = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let response = serve_embedded_str(&request);
+        assert_eq!(response.status, StatusCode::NotFound, "{}", path);
+    }
+}
+
+#[test]
+fn serve_embedded_serves_index_files() {
+    let response = serve_embedded_str("GET / HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(read_body(response), "<h1>root</h1>");
+
+    let response = serve_embedded_str("GET /docs/ HTTP/1.1\r\n\r\n");
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers.get_first("ETag"), Some("\"2\""));
+    assert_eq!(read_body(response), "<h1>docs</h1>");
+}
+
+#[test]
+fn serve_embedded_answers_conditional_requests() {
+    let response = serve_embedded_str("GET /app.js HTTP/1.1\r\nIf-None-Match: \"3\"\r\n\r\n");
+    assert_eq!(response.status, StatusCode::NotModified);
+    assert_eq!(response.headers.get_first("ETag"), Some("\"3\""));
+    assert!(response.body.is_none());
+
+    let response = serve_embedded_str("GET /app.js HTTP/1.1\r\nIf-None-Match: \"2\"\r\n\r\n");
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[test]
+fn serve_embedded_answers_range_requests() {
+    let response = serve_embedded_str("GET /app.js HTTP/1.1\r\nRange: bytes=0-6\r\n\r\n");
+    assert_eq!(response.status, StatusCode::PartialContent);
+    assert_eq!(
+        response.headers.get_first("Content-Range"),
+        Some("bytes 0-6/14")
+    );
+    assert_eq!(response.headers.get_first("ETag"), Some("\"3\""));
+    assert_eq!(read_body(response), "console");
+
+    let response = serve_embedded_str("GET /app.js HTTP/1.1\r\nRange: bytes=20-\r\n\r\n");
+    assert_eq!(response.status, StatusCode::RequestedRangeNotSatisfiable);
+    assert_eq!(
+        response.headers.get_first("Content-Range"),
+        Some("bytes */14")
+    );
+
+    // The range is only served if the If-Range entity-tag matches.
+    let request = "GET /app.js HTTP/1.1\r\nRange: bytes=0-6\r\nIf-Range: \"3\"\r\n\r\n";
+    let response = serve_embedded_str(request);
+    assert_eq!(response.status, StatusCode::PartialContent);
+    let request = "GET /app.js HTTP/1.1\r\nRange: bytes=0-6\r\nIf-Range: \"2\"\r\n\r\n";
+    let response = serve_embedded_str(request);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(read_body(response), "console.log(1)");
+}
+
+#[test]
+fn serve_embedded_answers_head_requests() {
+    let head = serve_embedded_str("HEAD /app.js HTTP/1.1\r\n\r\n");
+    let get = serve_embedded_str("GET /app.js HTTP/1.1\r\n\r\n");
+    assert_eq!(head.status, StatusCode::OK);
+    assert_eq!(head.headers.content_length(), Some(14));
+    for name in ["Content-Type", "Content-Length", "ETag", "Accept-Ranges"] {
+        assert_eq!(
+            head.headers.get_first(name),
+            get.headers.get_first(name),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn write_embedded_files_writes_the_table() {
+    let dir = TempDir::new("embedded");
+    dir.write("index.html", "hello");
+    dir.write("css/we\"ird\\name.css", "body {}");
+    dir.write(".env", "secret");
+    let to = TempDir::new("embedded_out");
+    let to = to.0.join("assets.rs");
+    write_embedded_files(&dir.0, &to).unwrap();
+
+    let table = fs::read_to_string(&to).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "&[");
+    let file = dir.0.join("css/we\"ird\\name.css");
+    assert_eq!(
+        lines[1],
+        format!(
+            "    wruster_handlers::EmbeddedFile {{ path: \"css/we\\\"ird\\\\name.css\", content: include_bytes!({:?}), mime: \"text/css\", etag: \"\\\"7-a9b342a0ff2b8953\\\"\" }},",
+            file.to_str().unwrap()
+        )
+    );
+    let file = dir.0.join("index.html");
+    assert_eq!(
+        lines[2],
+        format!(
+            "    wruster_handlers::EmbeddedFile {{ path: \"index.html\", content: include_bytes!({:?}), mime: \"text/html\", etag: \"\\\"5-a430d84680aabd0b\\\"\" }},",
+            file.to_str().unwrap()
+        )
+    );
+    assert_eq!(lines[3], "]");
+}